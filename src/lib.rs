@@ -1,147 +1,5690 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
+// Existe só pelo nome do crate, `INK`, mantido para não mudar o caminho usado
+// com `ink-as-dependency` nem o nome do contrato gerado. O aviso sobre o nome
+// do crate só pode ser desativado na raiz, o que vale para o crate inteiro.
+#![allow(non_snake_case)]
+
+pub mod psp22;
+pub mod psp34;
 
 #[ink::contract]
 mod biblioteca_storage {
+    use crate::psp22::{PSP22Error, PSP22};
+    use crate::psp34::{Id, PSP34Error, PSP34};
+    use ink::env::call::{build_call, ExecutionInput, Selector};
     use ink::prelude::vec::Vec;
     use ink::prelude::string::String;
-    use core::convert::TryFrom;
+    use ink::storage::traits::{ManualKey, StorageKey};
+    use ink::storage::{Lazy, Mapping};
 
-    /// Definição dos gêneros dos livros.
-    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    /// Versão atual do layout de armazenamento do contrato. A versão 1 é a do
+    /// primeiro código com `atualizar_codigo`; mudanças posteriores no layout
+    /// de `Livro` a incrementam e são convertidas por `migrar`.
+    pub const VERSAO_STORAGE: u16 = 1;
+
+    /// Tamanho máximo do título de um livro, em bytes.
+    pub const TAMANHO_MAXIMO_TITULO: usize = 256;
+
+    /// Tamanho máximo, em bytes, dos campos curtos: editora e ISBN nos
+    /// metadados e nacionalidade no registro de autores.
+    pub const TAMANHO_MAXIMO_CAMPO: usize = 128;
+
+    /// Tamanho máximo do resumo de um livro, em bytes.
+    pub const TAMANHO_MAXIMO_RESUMO: usize = 2048;
+
+    /// Tamanho máximo de uma tag, em bytes.
+    pub const TAMANHO_MAXIMO_TAG: usize = 32;
+
+    /// Quantidade máxima de autores e de tags de um livro.
+    pub const MAXIMO_ITENS_POR_LISTA: usize = 16;
+
+    /// Quantidade máxima de gêneros de um livro.
+    pub const MAXIMO_GENEROS_POR_LIVRO: usize = 8;
+
+    /// Quantidade máxima de livros retornados por página nas consultas
+    /// paginadas.
+    pub const MAXIMO_POR_PAGINA: u32 = 50;
+
+    /// Quantidade máxima de IDs examinados por página em
+    /// `listar_livros_paginado` e nas buscas por título, para limitar o custo
+    /// quando poucos IDs examinados entram no resultado.
+    const MAXIMO_IDS_POR_VARREDURA: u32 = 4 * MAXIMO_POR_PAGINA;
+
+    /// Tamanho mínimo, em caracteres, de um prefixo na busca por prefixo.
+    pub const TAMANHO_MINIMO_PREFIXO: usize = 2;
+
+    /// Tamanho máximo, em caracteres, das palavras-chave indexadas, para manter
+    /// as chaves do índice de títulos abaixo do limite de tamanho de chaves de
+    /// armazenamento. Palavras maiores são indexadas pelos seus primeiros
+    /// caracteres e conferidas no título.
+    pub const TAMANHO_MAXIMO_PALAVRA: usize = 24;
+
+    /// Tamanho máximo, em caracteres, dos prefixos indexados. Prefixos maiores
+    /// são buscados pelos seus primeiros caracteres e conferidos no título.
+    pub const TAMANHO_MAXIMO_PREFIXO: usize = 10;
+
+    /// Palavras ignoradas no índice de títulos.
+    const PALAVRAS_VAZIAS: [&str; 22] = [
+        "o", "a", "os", "as", "um", "uma", "uns", "umas", "de", "da", "do", "das", "dos", "e", "em", "no",
+        "na", "nos", "nas", "por", "para", "com",
+    ];
+
+    /// Identificador de um gênero no registro de gêneros.
+    pub type GeneroId = u32;
+
+    /// Identificador de um autor no registro de autores.
+    pub type AutorId = u32;
+
+    pub const GENERO_FICCAO: GeneroId = 0;
+    pub const GENERO_BIOGRAFIA: GeneroId = 1;
+    pub const GENERO_POESIA: GeneroId = 2;
+    pub const GENERO_INFANTIL: GeneroId = 3;
+    pub const GENERO_ROMANCE: GeneroId = 4;
+    pub const GENERO_OUTRO: GeneroId = 5;
+
+    /// Gêneros cadastrados na criação do contrato, com os mesmos valores da
+    /// antiga enumeração fixa de gêneros.
+    const GENEROS_INICIAIS: [(GeneroId, &str); 6] = [
+        (GENERO_FICCAO, "Ficção"),
+        (GENERO_BIOGRAFIA, "Biografia"),
+        (GENERO_POESIA, "Poesia"),
+        (GENERO_INFANTIL, "Infantil"),
+        (GENERO_ROMANCE, "Romance"),
+        (GENERO_OUTRO, "Outro"),
+    ];
+
+    /// Um dia, em milissegundos de `block_timestamp`.
+    pub const DIA: Timestamp = 24 * 60 * 60 * 1000;
+
+    /// Prazo padrão de um empréstimo para membros do nível `Basico`.
+    pub const PRAZO_EMPRESTIMO: Timestamp = 14 * DIA;
+
+    /// Tamanho máximo do nome de um membro, autor ou gênero, em bytes.
+    pub const TAMANHO_MAXIMO_NOME: usize = 64;
+
+    /// Quantidade máxima de exemplares de um mesmo título.
+    pub const MAXIMO_EXEMPLARES_POR_LIVRO: usize = 64;
+
+    /// Quantidade máxima de membros na fila de reservas de um título.
+    pub const MAXIMO_RESERVAS_POR_LIVRO: usize = 32;
+
+    /// Erros retornados pelas mensagens do contrato.
+    ///
+    /// Variantes novas entram sempre no fim, para não mudar os índices SCALE
+    /// das existentes entre versões do código.
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Eq)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
-    pub enum Genero {
-        Ficcao = 0,
-        Biografia = 1,
-        Poesia = 2,
-        Infantil = 3,
-        Romance = 4,
-        Outro = 5,
+    pub enum Error {
+        /// Nenhum livro cadastrado com o ID informado.
+        LivroNaoEncontrado,
+        /// O título informado está vazio.
+        TituloVazio,
+        /// O título informado excede `TAMANHO_MAXIMO_TITULO`.
+        TituloMuitoLongo,
+        /// A conta chamadora não tem permissão para a operação.
+        NaoAutorizado,
+        /// Não há mais IDs disponíveis para novos livros.
+        IdEsgotado,
+        /// Uma tag ou a nacionalidade de um autor está vazia.
+        CampoVazio,
+        /// Um campo de metadados excede o tamanho máximo permitido.
+        CampoMuitoLongo,
+        /// A lista de autores ou de tags excede `MAXIMO_ITENS_POR_LISTA`, ou a
+        /// lista de IDs de `obter_livros` excede `MAXIMO_POR_PAGINA`.
+        ListaMuitoLonga,
+        /// O idioma não é um código ISO 639 de duas ou três letras minúsculas.
+        IdiomaInvalido,
+        /// O ISBN tem formato ou dígito verificador inválido.
+        IsbnInvalido,
+        /// O ISBN-10 e o ISBN-13 informados não correspondem à mesma edição.
+        IsbnDivergente,
+        /// O ISBN já está cadastrado em outro livro.
+        IsbnDuplicado,
+        /// Nenhum gênero cadastrado com o ID informado.
+        GeneroNaoEncontrado,
+        /// O gênero foi descontinuado e não pode ser atribuído a novos livros.
+        GeneroDescontinuado,
+        /// Já existe um gênero com o nome informado.
+        GeneroDuplicado,
+        /// O livro precisa ter ao menos um gênero.
+        SemGenero,
+        /// O mesmo gênero foi informado mais de uma vez.
+        GeneroRepetido,
+        /// O saldo de pontos de leitura é insuficiente.
+        PontosInsuficientes,
+        /// Nenhum autor cadastrado com o ID informado.
+        AutorNaoEncontrado,
+        /// O mesmo autor foi informado mais de uma vez.
+        AutorRepetido,
+        /// O autor ainda é referenciado por livros do catálogo.
+        AutorReferenciado,
+        /// O ano de falecimento é anterior ao de nascimento.
+        AnosInvalidos,
+        /// O livro não está disponível para empréstimo.
+        LivroIndisponivel,
+        /// O livro não está emprestado.
+        LivroNaoEmprestado,
+        /// A operação não é permitida enquanto o livro está emprestado.
+        EmprestimoAtivo,
+        /// Nenhum exemplar cadastrado com o ID informado.
+        ExemplarNaoEncontrado,
+        /// O título já possui `MAXIMO_EXEMPLARES_POR_LIVRO` exemplares.
+        LimiteDeExemplares,
+        /// Há exemplar disponível; não é necessário reservar.
+        ExemplarDisponivel,
+        /// O chamador já está na fila ou tem um exemplar retido para si.
+        ReservaExistente,
+        /// O chamador não tem reserva para o título.
+        ReservaNaoEncontrada,
+        /// A fila de reservas do título atingiu `MAXIMO_RESERVAS_POR_LIVRO`.
+        FilaCheia,
+        /// A operação não é permitida enquanto o exemplar está retido para um membro.
+        ExemplarReservado,
+        /// Outro membro aguarda na fila de reservas do título.
+        ReservaPendente,
+        /// O empréstimo já foi renovado `maximo_renovacoes` vezes.
+        LimiteDeRenovacoes,
+        /// O membro tem multas pendentes acima de `limite_multa`.
+        MultasPendentes,
+        /// O valor informado ou transferido é inválido para a operação.
+        ValorInvalido,
+        /// Os fundos disponíveis não cobrem o valor solicitado.
+        SaldoInsuficiente,
+        /// A transferência de saldo nativo falhou.
+        FalhaNaTransferencia,
+        /// Nenhum token PSP22 de pagamento foi configurado.
+        TokenNaoConfigurado,
+        /// O saldo do pagador no token de pagamento é insuficiente.
+        SaldoDeTokenInsuficiente,
+        /// O pagador não autorizou o contrato a movimentar o valor no token de
+        /// pagamento.
+        PermissaoDeTokenInsuficiente,
+        /// A chamada ao token de pagamento falhou ou foi recusada.
+        FalhaNoToken,
+        /// O contrato está pausado.
+        ContratoPausado,
+        /// A conta não está registrada como membro.
+        MembroNaoRegistrado,
+        /// A conta já está registrada como membro.
+        MembroJaRegistrado,
+        /// O membro está suspenso ou expirado.
+        MembroInativo,
+        /// O nome informado está vazio.
+        NomeVazio,
+        /// O nome informado excede `TAMANHO_MAXIMO_NOME`.
+        NomeMuitoLongo,
+        /// O membro já atingiu o limite de empréstimos do seu nível.
+        LimiteDeEmprestimos,
+        /// A assinatura do membro expirou ou nunca foi comprada.
+        AssinaturaExpirada,
+        /// Há uma migração de armazenamento pendente; conclua-a com `migrar`.
+        MigracaoPendente,
+        /// Um registro antigo não pôde ser lido durante a migração.
+        FalhaNaMigracao,
+        /// A troca do código do contrato falhou.
+        FalhaNaAtualizacao,
     }
 
-    impl TryFrom<u8> for Genero {
-        type Error = &'static str;
+    /// Tipo de retorno das mensagens do contrato.
+    pub type Result<T> = core::result::Result<T, Error>;
 
-        fn try_from(value: u8) -> Result<Self, Self::Error> {
-            match value {
-                0 => Ok(Genero::Ficcao),
-                1 => Ok(Genero::Biografia),
-                2 => Ok(Genero::Poesia),
-                3 => Ok(Genero::Infantil),
-                4 => Ok(Genero::Romance),
-                5 => Ok(Genero::Outro),
-                _ => Err("Valor inválido para Genero"),
-            }
-        }
+    /// Papéis de acesso concedidos às contas.
+    ///
+    /// `Admin` implica todos os demais papéis.
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum Papel {
+        Admin,
+        Bibliotecario,
+        Membro,
+    }
+
+    /// Emitido quando um livro é adicionado ao catálogo.
+    #[ink(event)]
+    pub struct LivroAdicionado {
+        #[ink(topic)]
+        id: u32,
+        /// Gênero principal, o primeiro da lista de gêneros.
+        #[ink(topic)]
+        genero: GeneroId,
+        #[ink(topic)]
+        por: AccountId,
+        titulo: String,
+        generos: Vec<GeneroId>,
+    }
+
+    /// Emitido quando um livro é atualizado.
+    #[ink(event)]
+    pub struct LivroAtualizado {
+        #[ink(topic)]
+        id: u32,
+        /// Gênero principal após a atualização.
+        #[ink(topic)]
+        genero: GeneroId,
+        #[ink(topic)]
+        por: AccountId,
+        titulo_anterior: String,
+        titulo_novo: String,
+        generos_anteriores: Vec<GeneroId>,
+        generos_novos: Vec<GeneroId>,
+    }
+
+    /// Emitido quando um livro é removido do catálogo.
+    #[ink(event)]
+    pub struct LivroRemovido {
+        #[ink(topic)]
+        id: u32,
+        /// Gênero principal do livro removido.
+        #[ink(topic)]
+        genero: GeneroId,
+        #[ink(topic)]
+        por: AccountId,
+        titulo: String,
+    }
+
+    /// Emitido quando um exemplar é incluído no acervo.
+    #[ink(event)]
+    pub struct ExemplarAdicionado {
+        #[ink(topic)]
+        livro_id: u32,
+        #[ink(topic)]
+        exemplar_id: u32,
+        conservacao: Conservacao,
+    }
+
+    /// Emitido quando o token PSP34 de um exemplar é emitido, muda de
+    /// detentor ou é destruído.
+    #[ink(event)]
+    pub struct CustodiaTransferida {
+        #[ink(topic)]
+        de: Option<AccountId>,
+        #[ink(topic)]
+        para: Option<AccountId>,
+        #[ink(topic)]
+        id: Id,
+    }
+
+    /// Emitido quando um exemplar é retirado do acervo.
+    #[ink(event)]
+    pub struct ExemplarRetirado {
+        #[ink(topic)]
+        livro_id: u32,
+        #[ink(topic)]
+        exemplar_id: u32,
+    }
+
+    /// Emitido quando um exemplar é emprestado.
+    #[ink(event)]
+    pub struct LivroEmprestado {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        exemplar_id: u32,
+        #[ink(topic)]
+        tomador: AccountId,
+        vencimento: Timestamp,
+    }
+
+    /// Emitido quando um exemplar emprestado é devolvido.
+    #[ink(event)]
+    pub struct LivroDevolvido {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        exemplar_id: u32,
+        #[ink(topic)]
+        tomador: AccountId,
+    }
+
+    /// Emitido quando um empréstimo é renovado.
+    #[ink(event)]
+    pub struct EmprestimoRenovado {
+        #[ink(topic)]
+        exemplar_id: u32,
+        #[ink(topic)]
+        tomador: AccountId,
+        vencimento: Timestamp,
+        renovacoes: u8,
+    }
+
+    /// Emitido quando uma devolução em atraso gera multa.
+    #[ink(event)]
+    pub struct MultaAplicada {
+        #[ink(topic)]
+        membro: AccountId,
+        #[ink(topic)]
+        exemplar_id: u32,
+        valor: Balance,
+    }
+
+    /// Emitido quando um membro paga multas.
+    #[ink(event)]
+    pub struct MultaPaga {
+        #[ink(topic)]
+        membro: AccountId,
+        valor: Balance,
+        restante: Balance,
+    }
+
+    /// Emitido quando pontos de leitura são emitidos, transferidos ou gastos.
+    #[ink(event)]
+    pub struct PontosTransferidos {
+        #[ink(topic)]
+        de: Option<AccountId>,
+        #[ink(topic)]
+        para: Option<AccountId>,
+        valor: Balance,
+    }
+
+    /// Emitido quando uma conta autoriza outra a gastar seus pontos de leitura.
+    #[ink(event)]
+    pub struct PontosAprovados {
+        #[ink(topic)]
+        dono: AccountId,
+        #[ink(topic)]
+        gastador: AccountId,
+        valor: Balance,
+    }
+
+    /// Emitido quando um bibliotecário registra um dano a um exemplar emprestado.
+    #[ink(event)]
+    pub struct DanoRegistrado {
+        #[ink(topic)]
+        exemplar_id: u32,
+        #[ink(topic)]
+        tomador: AccountId,
+        valor: Balance,
+    }
+
+    /// Emitido quando a caução de um empréstimo é acertada na devolução.
+    #[ink(event)]
+    pub struct CaucaoDevolvida {
+        #[ink(topic)]
+        exemplar_id: u32,
+        #[ink(topic)]
+        tomador: AccountId,
+        reembolso: Balance,
+        retido: Balance,
+    }
+
+    /// Emitido quando o reembolso de uma caução falha e fica guardado para
+    /// saque com `sacar_reembolso`.
+    #[ink(event)]
+    pub struct ReembolsoPendente {
+        #[ink(topic)]
+        conta: AccountId,
+        moeda: Moeda,
+        valor: Balance,
+    }
+
+    /// Emitido quando um exemplar emprestado é dado como perdido.
+    #[ink(event)]
+    pub struct ExemplarPerdido {
+        #[ink(topic)]
+        livro_id: u32,
+        #[ink(topic)]
+        exemplar_id: u32,
+        #[ink(topic)]
+        tomador: AccountId,
+        caucao_retida: Balance,
+    }
+
+    /// Emitido quando o dono saca fundos arrecadados pelo contrato.
+    #[ink(event)]
+    pub struct FundosSacados {
+        #[ink(topic)]
+        por: AccountId,
+        #[ink(topic)]
+        destino: AccountId,
+        moeda: Moeda,
+        valor: Balance,
+        restante: Balance,
+    }
+
+    /// Emitido quando uma conta se registra como membro.
+    #[ink(event)]
+    pub struct MembroRegistrado {
+        #[ink(topic)]
+        conta: AccountId,
+        nome: String,
+    }
+
+    /// Emitido quando o perfil de um membro é alterado.
+    #[ink(event)]
+    pub struct MembroAtualizado {
+        #[ink(topic)]
+        conta: AccountId,
+        #[ink(topic)]
+        por: AccountId,
+        nome: String,
+        nivel: NivelMembro,
+    }
+
+    /// Emitido quando o status de um membro muda.
+    #[ink(event)]
+    pub struct StatusDoMembroAlterado {
+        #[ink(topic)]
+        conta: AccountId,
+        #[ink(topic)]
+        por: AccountId,
+        status: StatusMembro,
+    }
+
+    /// Emitido quando um membro compra ou renova a assinatura.
+    #[ink(event)]
+    pub struct AssinaturaPaga {
+        #[ink(topic)]
+        conta: AccountId,
+        #[ink(topic)]
+        nivel: NivelMembro,
+        valor: Balance,
+        expira_em: Timestamp,
+    }
+
+    /// Emitido quando um membro entra na fila de reservas de um título.
+    #[ink(event)]
+    pub struct ReservaRealizada {
+        #[ink(topic)]
+        livro_id: u32,
+        #[ink(topic)]
+        membro: AccountId,
+        posicao: u32,
+    }
+
+    /// Emitido quando um membro sai da fila ou abre mão do exemplar retido.
+    #[ink(event)]
+    pub struct ReservaCancelada {
+        #[ink(topic)]
+        livro_id: u32,
+        #[ink(topic)]
+        membro: AccountId,
+    }
+
+    /// Emitido quando um exemplar passa a ficar retido para o primeiro da fila.
+    #[ink(event)]
+    pub struct ExemplarRetido {
+        #[ink(topic)]
+        livro_id: u32,
+        #[ink(topic)]
+        exemplar_id: u32,
+        #[ink(topic)]
+        membro: AccountId,
+        expira_em: Timestamp,
+    }
+
+    /// Emitido quando a configuração do contrato é alterada.
+    #[ink(event)]
+    pub struct ConfiguracaoAtualizada {
+        #[ink(topic)]
+        por: AccountId,
+    }
+
+    /// Emitido quando um gênero é criado.
+    #[ink(event)]
+    pub struct GeneroCriado {
+        #[ink(topic)]
+        id: GeneroId,
+        #[ink(topic)]
+        pai: Option<GeneroId>,
+        nome: String,
+    }
+
+    /// Emitido quando um gênero é renomeado.
+    #[ink(event)]
+    pub struct GeneroRenomeado {
+        #[ink(topic)]
+        id: GeneroId,
+        nome_anterior: String,
+        nome_novo: String,
+    }
+
+    /// Emitido quando um gênero é descontinuado.
+    #[ink(event)]
+    pub struct GeneroDescontinuado {
+        #[ink(topic)]
+        id: GeneroId,
+    }
+
+    /// Emitido quando um autor é cadastrado.
+    #[ink(event)]
+    pub struct AutorAdicionado {
+        #[ink(topic)]
+        id: AutorId,
+        #[ink(topic)]
+        por: AccountId,
+        nome: String,
+    }
+
+    /// Emitido quando os dados de um autor são alterados.
+    #[ink(event)]
+    pub struct AutorAtualizado {
+        #[ink(topic)]
+        id: AutorId,
+        #[ink(topic)]
+        por: AccountId,
+        nome: String,
+    }
+
+    /// Emitido quando um autor é removido.
+    #[ink(event)]
+    pub struct AutorRemovido {
+        #[ink(topic)]
+        id: AutorId,
+        #[ink(topic)]
+        por: AccountId,
+        nome: String,
+    }
+
+    /// Emitido quando o contrato é pausado.
+    #[ink(event)]
+    pub struct Pausado {
+        #[ink(topic)]
+        por: AccountId,
+        /// Instante em que o contrato volta a operar sozinho, se definido.
+        ate: Option<Timestamp>,
+    }
+
+    /// Emitido quando o contrato é despausado por um administrador.
+    #[ink(event)]
+    pub struct Despausado {
+        #[ink(topic)]
+        por: AccountId,
+    }
+
+    /// Emitido quando um papel é concedido a uma conta.
+    #[ink(event)]
+    pub struct PapelConcedido {
+        #[ink(topic)]
+        papel: Papel,
+        #[ink(topic)]
+        conta: AccountId,
+        por: AccountId,
+    }
+
+    /// Emitido quando um papel é revogado ou renunciado.
+    #[ink(event)]
+    pub struct PapelRevogado {
+        #[ink(topic)]
+        papel: Papel,
+        #[ink(topic)]
+        conta: AccountId,
+        por: AccountId,
+    }
+
+    /// Emitido quando o dono propõe a transferência do contrato.
+    #[ink(event)]
+    pub struct TransferenciaDePropriedadeIniciada {
+        #[ink(topic)]
+        dono_atual: AccountId,
+        #[ink(topic)]
+        novo_dono: AccountId,
+    }
+
+    /// Emitido quando o novo dono aceita a transferência.
+    #[ink(event)]
+    pub struct PropriedadeTransferida {
+        #[ink(topic)]
+        dono_anterior: AccountId,
+        #[ink(topic)]
+        novo_dono: AccountId,
+    }
+
+    /// Emitido quando o dono troca o código do contrato.
+    #[ink(event)]
+    pub struct CodigoAtualizado {
+        #[ink(topic)]
+        por: AccountId,
+        code_hash: Hash,
+    }
+
+    /// Emitido quando a migração de armazenamento termina.
+    #[ink(event)]
+    pub struct MigracaoConcluida {
+        versao_anterior: u16,
+        versao_nova: u16,
+    }
+
+    /// Gênero do registro de gêneros.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Genero {
+        id: GeneroId,
+        nome: String,
+        /// Gênero do qual este é subgênero.
+        pai: Option<GeneroId>,
+        /// Gêneros descontinuados continuam nos livros que já os têm, mas não
+        /// podem ser atribuídos a outros livros.
+        descontinuado: bool,
     }
 
     /// Estrutura de um livro.
     #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
-    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
     pub struct Livro {
         id: u32,
         titulo: String,
-        genero: Genero,
+        /// Gêneros do livro; o primeiro é o gênero principal.
+        generos: Vec<GeneroId>,
+        metadados: Metadados,
+    }
+
+    /// Autor do registro de autores.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Autor {
+        id: AutorId,
+        nome: String,
+        ano_nascimento: Option<u16>,
+        ano_falecimento: Option<u16>,
+        nacionalidade: Option<String>,
+    }
+
+    /// Dados bibliográficos complementares de um livro.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, Default, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Metadados {
+        /// IDs do registro de autores.
+        pub autores: Vec<AutorId>,
+        pub isbn10: Option<String>,
+        pub isbn13: Option<String>,
+        pub editora: Option<String>,
+        pub ano_publicacao: Option<u16>,
+        /// Código ISO 639 do idioma, como "pt" ou "por".
+        pub idioma: Option<String>,
+        pub edicao: Option<u16>,
+        pub paginas: Option<u32>,
+        pub resumo: Option<String>,
+        pub tags: Vec<String>,
+    }
+
+    /// ISBN normalizado para a forma ISBN-13, usado como chave do índice de ISBNs.
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Isbn([u8; 13]);
+
+    impl Isbn {
+        /// Interpreta um ISBN-10 ou ISBN-13, ignorando hífens e espaços, e
+        /// valida o dígito verificador.
+        pub fn analisar(texto: &str) -> Result<Self> {
+            let mut digitos = [0u8; 13];
+            let mut tamanho = 0;
+            for c in texto.bytes().filter(|c| *c != b'-' && *c != b' ') {
+                if tamanho == digitos.len() {
+                    return Err(Error::IsbnInvalido);
+                }
+                digitos[tamanho] = c;
+                tamanho += 1;
+            }
+            match tamanho {
+                10 => Self::de_isbn10(&digitos[..10]),
+                13 => Self::de_isbn13(&digitos),
+                _ => Err(Error::IsbnInvalido),
+            }
+        }
+
+        /// Retorna os 13 dígitos do ISBN.
+        pub fn como_str(&self) -> &str {
+            // Só contém dígitos ASCII, garantidos por `analisar`.
+            core::str::from_utf8(&self.0).unwrap_or_default()
+        }
+
+        fn de_isbn10(digitos: &[u8]) -> Result<Self> {
+            let mut soma = 0u32;
+            for (i, c) in digitos.iter().enumerate() {
+                let valor = match c {
+                    b'0'..=b'9' => u32::from(c - b'0'),
+                    b'X' | b'x' if i == 9 => 10,
+                    _ => return Err(Error::IsbnInvalido),
+                };
+                soma += (10 - i as u32) * valor;
+            }
+            if !soma.is_multiple_of(11) {
+                return Err(Error::IsbnInvalido);
+            }
+            let mut isbn13 = *b"9780000000000";
+            isbn13[3..12].copy_from_slice(&digitos[..9]);
+            isbn13[12] = b'0' + digito_verificador_isbn13(&isbn13);
+            Ok(Self(isbn13))
+        }
+
+        fn de_isbn13(digitos: &[u8; 13]) -> Result<Self> {
+            if !digitos.iter().all(u8::is_ascii_digit) {
+                return Err(Error::IsbnInvalido);
+            }
+            if digitos[12] - b'0' != digito_verificador_isbn13(digitos) {
+                return Err(Error::IsbnInvalido);
+            }
+            Ok(Self(*digitos))
+        }
+    }
+
+    /// Calcula o dígito verificador a partir dos 12 primeiros dígitos de um ISBN-13.
+    fn digito_verificador_isbn13(digitos: &[u8; 13]) -> u8 {
+        let soma: u32 = digitos[..12]
+            .iter()
+            .enumerate()
+            .map(|(i, c)| u32::from(c - b'0') * if i % 2 == 0 { 1 } else { 3 })
+            .sum();
+        ((10 - soma % 10) % 10) as u8
+    }
+
+    /// Alterações parciais de um livro; campos `None` são mantidos.
+    ///
+    /// Nos campos opcionais do livro, `Some(None)` apaga o valor atual.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct AtualizacaoLivro {
+        pub titulo: Option<String>,
+        pub generos: Option<Vec<GeneroId>>,
+        pub autores: Option<Vec<AutorId>>,
+        pub isbn10: Option<Option<String>>,
+        pub isbn13: Option<Option<String>>,
+        pub editora: Option<Option<String>>,
+        pub ano_publicacao: Option<Option<u16>>,
+        pub idioma: Option<Option<String>>,
+        pub edicao: Option<Option<u16>>,
+        pub paginas: Option<Option<u32>>,
+        pub resumo: Option<Option<String>>,
+        pub tags: Option<Vec<String>>,
+    }
+
+    /// Situação de um exemplar no acervo.
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum StatusExemplar {
+        Disponivel,
+        Emprestado,
+        Reservado,
+        Perdido,
+    }
+
+    /// Estado de conservação de um exemplar.
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum Conservacao {
+        Novo,
+        Bom,
+        Regular,
+        Danificado,
+    }
+
+    /// Cópia física de um título.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Exemplar {
+        id: u32,
+        livro_id: u32,
+        conservacao: Conservacao,
+        status: StatusExemplar,
+    }
+
+    /// Registro de um empréstimo em andamento.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Emprestimo {
+        tomador: AccountId,
+        inicio: Timestamp,
+        vencimento: Timestamp,
+        /// Quantas vezes o empréstimo já foi renovado.
+        renovacoes: u8,
+        /// Caução paga na retirada, devolvida ao fim do empréstimo.
+        caucao: Balance,
+        /// Danos registrados durante o empréstimo, descontados da caução.
+        dano: Balance,
+        /// Moeda em que a caução foi paga e em que é devolvida, com o token
+        /// usado na retirada mesmo que a configuração mude depois.
+        moeda_da_caucao: Moeda,
+    }
+
+    /// Moeda de um pagamento ao contrato.
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum Moeda {
+        /// Saldo nativo da rede, transferido junto com a mensagem.
+        Nativa,
+        /// Token PSP22 da conta informada, cobrado por `transfer_from` com os
+        /// mesmos valores do saldo nativo. Os pagamentos usam o token
+        /// configurado em `token_de_pagamento` no momento da cobrança.
+        Token(AccountId),
+    }
+
+    /// Categoria de um membro, que determina suas regras de empréstimo.
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum NivelMembro {
+        Basico,
+        Estudante,
+        Premium,
+    }
+
+    /// Situação do cadastro de um membro.
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum StatusMembro {
+        Ativo,
+        Suspenso,
+        Expirado,
+    }
+
+    /// Cadastro de um membro da biblioteca.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Membro {
+        nome: String,
+        /// Momento do registro.
+        desde: Timestamp,
+        nivel: NivelMembro,
+        status: StatusMembro,
+        /// Fim da assinatura paga; zero se nunca houve assinatura.
+        expira_em: Timestamp,
+    }
+
+    /// Regras de empréstimo de um nível de membro.
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct RegrasNivel {
+        /// Quantidade máxima de empréstimos simultâneos.
+        pub maximo_emprestimos: u32,
+        /// Prazo, em milissegundos, de cada empréstimo.
+        pub prazo_emprestimo: Timestamp,
+    }
+
+    /// Exemplar retido para o membro no início da fila de reservas.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Retencao {
+        membro: AccountId,
+        /// Fim da janela de retirada; depois dela o exemplar passa ao próximo da fila.
+        expira_em: Timestamp,
+    }
+
+    /// Parâmetros ajustáveis pelos administradores.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Configuracao {
+        /// Tempo, em milissegundos, que um exemplar devolvido fica retido para o
+        /// primeiro membro da fila de reservas.
+        pub janela_retirada: Timestamp,
+        /// Prazo, em milissegundos, acrescentado ao vencimento a cada renovação.
+        pub prazo_renovacao: Timestamp,
+        /// Quantidade máxima de renovações de um mesmo empréstimo.
+        pub maximo_renovacoes: u8,
+        /// Multa cobrada por dia de atraso, ou fração de dia.
+        pub multa_por_dia: Balance,
+        /// Atraso, em milissegundos, tolerado sem multa. Passada a carência, a
+        /// multa conta desde o vencimento.
+        pub carencia: Timestamp,
+        /// Multas pendentes acima deste valor impedem novos empréstimos.
+        pub limite_multa: Balance,
+        pub regras_basico: RegrasNivel,
+        pub regras_estudante: RegrasNivel,
+        pub regras_premium: RegrasNivel,
+        /// Duração, em milissegundos, de cada período de assinatura.
+        pub duracao_assinatura: Timestamp,
+        pub preco_basico: Balance,
+        pub preco_estudante: Balance,
+        pub preco_premium: Balance,
+        /// Taxa cobrada a cada reserva, paga no saldo nativo ou em pontos.
+        pub taxa_reserva: Balance,
+        /// Pontos de leitura emitidos a cada devolução feita até o vencimento.
+        pub pontos_por_devolucao: Balance,
+        /// Duração mínima, em milissegundos, de um empréstimo para que a
+        /// devolução renda pontos.
+        pub duracao_minima_para_pontos: Timestamp,
+        /// Saldo máximo de pontos que uma conta pode acumular, por devoluções
+        /// ou por transferências recebidas.
+        pub maximo_pontos_por_membro: Balance,
+        /// Quantidade máxima de pontos em circulação.
+        pub maximo_pontos_em_circulacao: Balance,
+        /// Valor, no saldo nativo, de cada ponto gasto em multas e taxas.
+        pub valor_do_ponto: Balance,
+        /// Contrato PSP22 aceito para assinaturas, multas e cauções.
+        pub token_de_pagamento: Option<AccountId>,
+    }
+
+    impl Configuracao {
+        /// Retorna as regras de empréstimo de `nivel`.
+        pub fn regras(&self, nivel: NivelMembro) -> RegrasNivel {
+            match nivel {
+                NivelMembro::Basico => self.regras_basico,
+                NivelMembro::Estudante => self.regras_estudante,
+                NivelMembro::Premium => self.regras_premium,
+            }
+        }
+
+        /// Retorna o preço de um período de assinatura de `nivel`.
+        pub fn preco(&self, nivel: NivelMembro) -> Balance {
+            match nivel {
+                NivelMembro::Basico => self.preco_basico,
+                NivelMembro::Estudante => self.preco_estudante,
+                NivelMembro::Premium => self.preco_premium,
+            }
+        }
+    }
+
+    impl Default for Configuracao {
+        fn default() -> Self {
+            Self {
+                janela_retirada: 3 * DIA,
+                prazo_renovacao: 7 * DIA,
+                maximo_renovacoes: 2,
+                multa_por_dia: 1_000,
+                carencia: DIA,
+                limite_multa: 10_000,
+                regras_basico: RegrasNivel {
+                    maximo_emprestimos: 3,
+                    prazo_emprestimo: PRAZO_EMPRESTIMO,
+                },
+                regras_estudante: RegrasNivel {
+                    maximo_emprestimos: 5,
+                    prazo_emprestimo: 21 * DIA,
+                },
+                regras_premium: RegrasNivel {
+                    maximo_emprestimos: 10,
+                    prazo_emprestimo: 30 * DIA,
+                },
+                duracao_assinatura: 365 * DIA,
+                preco_basico: 0,
+                preco_estudante: 0,
+                preco_premium: 0,
+                taxa_reserva: 0,
+                pontos_por_devolucao: 10,
+                duracao_minima_para_pontos: DIA,
+                maximo_pontos_por_membro: 1_000,
+                maximo_pontos_em_circulacao: 1_000_000,
+                valor_do_ponto: 10,
+                token_de_pagamento: None,
+            }
+        }
     }
 
     #[ink(storage)]
     pub struct BibliotecaStorage {
-        livros: Vec<Livro>,
+        /// Livros indexados pelo ID.
+        livros: Mapping<u32, Livro>,
+        /// Índice posição → ID, usado para percorrer o catálogo.
+        ids: Mapping<u32, u32>,
+        /// Índice ID → posição, usado para remover livros sem varrer o catálogo.
+        posicoes: Mapping<u32, u32>,
+        total_livros: u32,
         proximo_id: u32,
+        /// Versão do layout de armazenamento, usada em migrações futuras.
+        versao_storage: u16,
+        /// Indica se o contrato foi pausado.
+        pausado: bool,
+        /// Instante a partir do qual a pausa deixa de valer, se definido.
+        pausado_ate: Option<Timestamp>,
+        /// Dono do contrato.
+        dono: AccountId,
+        /// Conta indicada pelo dono que ainda precisa aceitar a transferência.
+        dono_pendente: Option<AccountId>,
+        /// Papéis concedidos, indexados por (papel, conta).
+        papeis: Mapping<(Papel, AccountId), ()>,
+        /// Exemplares indexados pelo ID do exemplar.
+        exemplares: Mapping<u32, Exemplar>,
+        /// IDs dos exemplares de cada título, indexados pelo ID do livro.
+        exemplares_por_livro: Mapping<u32, Vec<u32>>,
+        proximo_exemplar_id: u32,
+        /// Empréstimos em andamento, indexados pelo ID do exemplar.
+        emprestimos: Mapping<u32, Emprestimo>,
+        /// Fila de reservas (FIFO) de cada título, indexada pelo ID do livro.
+        reservas: Mapping<u32, Vec<AccountId>>,
+        /// Exemplares retidos para retirada, indexados pelo ID do exemplar.
+        retencoes: Mapping<u32, Retencao>,
+        configuracao: Configuracao,
+        /// Multas pendentes de cada membro.
+        multas: Mapping<AccountId, Balance>,
+        /// Saldo arrecadado que o dono pode sacar. Não inclui cauções retidas.
+        fundos_disponiveis: Balance,
+        /// Saldo arrecadado no token de pagamento disponível para saque.
+        fundos_em_token: Mapping<AccountId, Balance>,
+        /// Caução exigida para emprestar cada título, indexada pelo ID do livro.
+        caucoes: Mapping<u32, Balance>,
+        /// Reembolsos de caução que falharam na devolução, indexados por
+        /// (tomador, moeda), à espera de `sacar_reembolso`.
+        reembolsos_pendentes: Mapping<(AccountId, Moeda), Balance>,
+        /// Índice de unicidade de ISBNs, do ISBN-13 normalizado para o ID do livro.
+        isbns: Mapping<Isbn, u32>,
+        /// Registro de gêneros, indexado pelo ID.
+        generos: Mapping<GeneroId, Genero>,
+        /// Índice de nomes de gêneros, usado para impedir nomes repetidos.
+        ids_de_generos: Mapping<String, GeneroId>,
+        proximo_genero_id: GeneroId,
+        /// Índice (gênero, posição) → ID do livro, usado para listar por gênero.
+        livros_por_genero: Mapping<(GeneroId, u32), u32>,
+        /// Índice (gênero, ID do livro) → posição em `livros_por_genero`.
+        posicoes_por_genero: Mapping<(GeneroId, u32), u32>,
+        /// Quantidade de livros de cada gênero.
+        total_por_genero: Mapping<GeneroId, u32>,
+        /// Índice (palavra-chave, posição) → ID do livro, montado a partir dos
+        /// títulos normalizados.
+        livros_por_palavra: Mapping<(String, u32), u32>,
+        /// Índice (palavra-chave, ID do livro) → posição em `livros_por_palavra`.
+        posicoes_por_palavra: Mapping<(String, u32), u32>,
+        /// Quantidade de livros com cada palavra-chave.
+        total_por_palavra: Mapping<String, u32>,
+        /// Índice (prefixo, posição) → ID do livro, com os prefixos das
+        /// palavras-chave de cada título.
+        livros_por_prefixo: Mapping<(String, u32), u32>,
+        /// Índice (prefixo, ID do livro) → posição em `livros_por_prefixo`.
+        posicoes_por_prefixo: Mapping<(String, u32), u32>,
+        /// Quantidade de livros com cada prefixo.
+        total_por_prefixo: Mapping<String, u32>,
+        /// Detentor do token PSP34 de cada exemplar: o próprio contrato, ou o
+        /// tomador enquanto o exemplar está emprestado.
+        detentores: Mapping<u32, AccountId>,
+        /// Quantidade de tokens PSP34 de cada conta.
+        tokens_por_conta: Mapping<AccountId, u32>,
+        total_de_tokens: u128,
+        /// Saldo de pontos de leitura de cada conta.
+        pontos: Mapping<AccountId, Balance>,
+        /// Pontos que o gastador pode transferir em nome do dono, indexados
+        /// por (dono, gastador).
+        permissoes_de_pontos: Mapping<(AccountId, AccountId), Balance>,
+        pontos_em_circulacao: Balance,
+        /// Registro de autores, indexado pelo ID.
+        autores: Mapping<AutorId, Autor>,
+        proximo_autor_id: AutorId,
+        /// Índice (autor, posição) → ID do livro, usado para listar por autor.
+        livros_por_autor: Mapping<(AutorId, u32), u32>,
+        /// Índice (autor, ID do livro) → posição em `livros_por_autor`.
+        posicoes_por_autor: Mapping<(AutorId, u32), u32>,
+        /// Quantidade de livros de cada autor.
+        total_por_autor: Mapping<AutorId, u32>,
+        /// Cadastro de membros, indexado pela conta.
+        membros: Mapping<AccountId, Membro>,
+        /// Quantidade de empréstimos em andamento de cada membro.
+        emprestimos_ativos: Mapping<AccountId, u32>,
+        // Os campos acima formam a raiz gravada pelo código anterior a
+        // `atualizar_codigo`. Campos novos ficam em `Lazy` ou `Mapping` com
+        // chave explícita, que não mudam a codificação da raiz.
+        /// Próximo ID de livro a converter pela migração em andamento.
+        migracao_ate: Lazy<u32, ManualKey<0x4d49_4752>>,
     }
 
     impl BibliotecaStorage {
         /// Construtor do contrato.
+        ///
+        /// A conta que instancia o contrato se torna dona e recebe o papel `Admin`.
         #[ink(constructor)]
         pub fn new() -> Self {
+            let dono = Self::env().caller();
+            let mut papeis = Mapping::default();
+            papeis.insert((Papel::Admin, dono), &());
+            let mut generos = Mapping::default();
+            let mut ids_de_generos = Mapping::default();
+            for (id, nome) in GENEROS_INICIAIS {
+                let nome = String::from(nome);
+                ids_de_generos.insert(&nome, &id);
+                generos.insert(
+                    id,
+                    &Genero {
+                        id,
+                        nome,
+                        pai: None,
+                        descontinuado: false,
+                    },
+                );
+            }
             Self {
-                livros: Vec::new(),
+                livros: Mapping::default(),
+                ids: Mapping::default(),
+                posicoes: Mapping::default(),
+                total_livros: 0,
                 proximo_id: 1,
+                versao_storage: VERSAO_STORAGE,
+                pausado: false,
+                pausado_ate: None,
+                dono,
+                dono_pendente: None,
+                papeis,
+                exemplares: Mapping::default(),
+                exemplares_por_livro: Mapping::default(),
+                proximo_exemplar_id: 1,
+                emprestimos: Mapping::default(),
+                reservas: Mapping::default(),
+                retencoes: Mapping::default(),
+                configuracao: Configuracao::default(),
+                multas: Mapping::default(),
+                fundos_disponiveis: 0,
+                fundos_em_token: Mapping::default(),
+                caucoes: Mapping::default(),
+                reembolsos_pendentes: Mapping::default(),
+                isbns: Mapping::default(),
+                generos,
+                ids_de_generos,
+                proximo_genero_id: GENEROS_INICIAIS.len() as GeneroId,
+                livros_por_genero: Mapping::default(),
+                posicoes_por_genero: Mapping::default(),
+                total_por_genero: Mapping::default(),
+                livros_por_palavra: Mapping::default(),
+                posicoes_por_palavra: Mapping::default(),
+                total_por_palavra: Mapping::default(),
+                livros_por_prefixo: Mapping::default(),
+                posicoes_por_prefixo: Mapping::default(),
+                total_por_prefixo: Mapping::default(),
+                detentores: Mapping::default(),
+                tokens_por_conta: Mapping::default(),
+                total_de_tokens: 0,
+                pontos: Mapping::default(),
+                permissoes_de_pontos: Mapping::default(),
+                pontos_em_circulacao: 0,
+                autores: Mapping::default(),
+                proximo_autor_id: 1,
+                livros_por_autor: Mapping::default(),
+                posicoes_por_autor: Mapping::default(),
+                total_por_autor: Mapping::default(),
+                membros: Mapping::default(),
+                emprestimos_ativos: Mapping::default(),
+                migracao_ate: Lazy::new(),
             }
         }
 
-        /// Adiciona um novo livro à biblioteca.
+        /// Retorna o dono do contrato.
         #[ink(message)]
-        pub fn adicionar_livro(&mut self, titulo: String, genero: Genero) -> u32 {
-            let livro = Livro {
-                id: self.proximo_id,
-                titulo,
-                genero,
-            };
-            self.livros.push(livro);
-            let id_atual = self.proximo_id;
-            self.proximo_id = self.proximo_id.saturating_add(1);
-            id_atual // Retorna o ID do livro adicionado
+        pub fn owner(&self) -> AccountId {
+            self.dono
         }
 
-        /// Retorna a lista de livros cadastrados.
+        /// Retorna a conta com transferência de propriedade pendente, se houver.
         #[ink(message)]
-        pub fn listar_livros(&self) -> Vec<Livro> {
-            self.livros.clone()
+        pub fn pending_owner(&self) -> Option<AccountId> {
+            self.dono_pendente
         }
 
-        /// Atualiza um livro existente pelo ID.
+        /// Inicia a transferência do contrato; `novo_dono` precisa aceitá-la.
         #[ink(message)]
-        pub fn atualizar_livro(&mut self, id: u32, novo_titulo: String, novo_genero: Genero) -> bool {
-            for livro in &mut self.livros {
-                if livro.id == id {
-                    livro.titulo = novo_titulo;
-                    livro.genero = novo_genero;
-                    return true;
-                }
+        pub fn transfer_ownership(&mut self, novo_dono: AccountId) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.garantir_dono()?;
+            self.dono_pendente = Some(novo_dono);
+            self.env().emit_event(TransferenciaDePropriedadeIniciada {
+                dono_atual: self.dono,
+                novo_dono,
+            });
+            Ok(())
+        }
+
+        /// Aceita a transferência pendente; o novo dono recebe o papel `Admin`
+        /// e o dono anterior o perde.
+        #[ink(message)]
+        pub fn accept_ownership(&mut self) -> Result<()> {
+            self.garantir_em_operacao()?;
+            let chamador = self.env().caller();
+            if self.dono_pendente != Some(chamador) {
+                return Err(Error::NaoAutorizado);
             }
-            false
+            let dono_anterior = self.dono;
+            self.dono = chamador;
+            self.dono_pendente = None;
+            self.revogar(Papel::Admin, dono_anterior);
+            self.conceder(Papel::Admin, chamador);
+            self.env().emit_event(PropriedadeTransferida {
+                dono_anterior,
+                novo_dono: chamador,
+            });
+            Ok(())
+        }
+
+        /// Retorna a configuração atual do contrato.
+        #[ink(message)]
+        pub fn configuracao(&self) -> Configuracao {
+            self.configuracao.clone()
         }
 
-        /// Remove um livro pelo ID.
+        /// Pausa o contrato, bloqueando todas as mensagens que alteram o estado
+        /// exceto `despausar`, `atualizar_codigo` e `migrar`. Exige `Admin`.
+        ///
+        /// Se `ate` for informado, a pausa termina sozinha nesse instante.
         #[ink(message)]
-        pub fn remover_livro(&mut self, id: u32) -> bool {
-            if let Some(index) = self.livros.iter().position(|livro| livro.id == id) {
-                self.livros.remove(index);
-                return true;
+        pub fn pausar(&mut self, ate: Option<Timestamp>) -> Result<()> {
+            self.garantir_papel(Papel::Admin)?;
+            if ate.is_some_and(|ate| ate <= self.env().block_timestamp()) {
+                return Err(Error::ValorInvalido);
             }
-            false
+            self.pausado = true;
+            self.pausado_ate = ate;
+            self.env().emit_event(Pausado {
+                por: self.env().caller(),
+                ate,
+            });
+            Ok(())
         }
-    }
 
-    /// Testes para verificar o funcionamento do contrato
-    #[cfg(test)]
-    mod tests {
-        use super::*;
+        /// Encerra a pausa do contrato. Exige `Admin`.
+        #[ink(message)]
+        pub fn despausar(&mut self) -> Result<()> {
+            self.garantir_papel(Papel::Admin)?;
+            self.pausado = false;
+            self.pausado_ate = None;
+            self.env().emit_event(Despausado {
+                por: self.env().caller(),
+            });
+            Ok(())
+        }
 
-        #[ink::test]
-        fn test_adicionar_livro() {
-            let mut contract = BibliotecaStorage::new();
-            let id = contract.adicionar_livro("Livro A".into(), Genero::Ficcao);
-            assert_eq!(id, 1);
+        /// Indica se o contrato está pausado no instante atual.
+        #[ink(message)]
+        pub fn pausado(&self) -> bool {
+            self.pausado
+                && self
+                    .pausado_ate
+                    .is_none_or(|ate| self.env().block_timestamp() < ate)
         }
 
-        #[ink::test]
-        fn test_listar_livros() {
-            let mut contract = BibliotecaStorage::new();
-            contract.adicionar_livro("Livro A".into(), Genero::Ficcao);
-            let livros = contract.listar_livros();
-            assert_eq!(livros.len(), 1);
-            assert_eq!(livros[0].titulo, "Livro A");
+        /// Substitui a configuração do contrato. Exige `Admin`.
+        #[ink(message)]
+        pub fn definir_configuracao(&mut self, configuracao: Configuracao) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.garantir_papel(Papel::Admin)?;
+            self.configuracao = configuracao;
+            self.env().emit_event(ConfiguracaoAtualizada {
+                por: self.env().caller(),
+            });
+            Ok(())
         }
 
-        #[ink::test]
-        fn test_atualizar_livro() {
-            let mut contract = BibliotecaStorage::new();
-            let id = contract.adicionar_livro("Antigo".into(), Genero::Ficcao);
-            let atualizado = contract.atualizar_livro(id, "Novo".into(), Genero::Romance);
-            assert!(atualizado);
-            let livros = contract.listar_livros();
-            assert_eq!(livros[0].titulo, "Novo");
+        /// Verifica se `conta` possui `papel`.
+        #[ink(message)]
+        pub fn has_role(&self, papel: Papel, conta: AccountId) -> bool {
+            self.papeis.contains((papel, conta))
         }
 
-        #[ink::test]
-        fn test_remover_livro() {
-            let mut contract = BibliotecaStorage::new();
-            let id = contract.adicionar_livro("Livro Removível".into(), Genero::Outro);
-            assert_eq!(contract.listar_livros().len(), 1);
-            let removido = contract.remover_livro(id);
-            assert!(removido);
-            assert_eq!(contract.listar_livros().len(), 0);
+        /// Concede `papel` a `conta`.
+        ///
+        /// Exige `Admin`; conceder `Admin` é exclusivo do dono.
+        #[ink(message)]
+        pub fn grant_role(&mut self, papel: Papel, conta: AccountId) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.garantir_gestor_de(papel)?;
+            self.conceder(papel, conta);
+            Ok(())
+        }
+
+        /// Revoga `papel` de `conta`.
+        ///
+        /// Exige `Admin`; revogar `Admin` é exclusivo do dono.
+        #[ink(message)]
+        pub fn revoke_role(&mut self, papel: Papel, conta: AccountId) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.garantir_gestor_de(papel)?;
+            self.revogar(papel, conta);
+            Ok(())
+        }
+
+        /// Renuncia a um papel da própria conta chamadora.
+        #[ink(message)]
+        pub fn renounce_role(&mut self, papel: Papel) -> Result<()> {
+            self.garantir_em_operacao()?;
+            let chamador = self.env().caller();
+            self.revogar(papel, chamador);
+            Ok(())
+        }
+
+        /// Adiciona um novo livro à biblioteca, com um primeiro exemplar.
+        ///
+        /// O primeiro de `generos` é o gênero principal. Os ISBNs informados
+        /// são validados e normalizados; o ISBN-13 é preenchido a partir do
+        /// ISBN-10 quando ausente.
+        #[ink(message)]
+        pub fn adicionar_livro(&mut self, titulo: String, generos: Vec<GeneroId>, metadados: Metadados) -> Result<u32> {
+            self.garantir_em_operacao()?;
+            self.garantir_papel(Papel::Bibliotecario)?;
+            validar_titulo(&titulo)?;
+            self.validar_generos(&generos, &[])?;
+            validar_metadados(&metadados)?;
+            self.validar_autores(&metadados.autores)?;
+            let mut metadados = metadados;
+            let isbn = normalizar_isbn(&mut metadados)?;
+            if isbn.is_some_and(|isbn| self.isbns.contains(isbn)) {
+                return Err(Error::IsbnDuplicado);
+            }
+            let id_atual = self.proximo_id;
+            let proximo_id = id_atual.checked_add(1).ok_or(Error::IdEsgotado)?;
+            let livro = Livro {
+                id: id_atual,
+                titulo,
+                generos,
+                metadados,
+            };
+            let exemplar_id = self.reservar_id_exemplar()?;
+            if let Some(isbn) = isbn {
+                self.isbns.insert(isbn, &id_atual);
+            }
+            self.livros.insert(id_atual, &livro);
+            self.ids.insert(self.total_livros, &id_atual);
+            self.posicoes.insert(id_atual, &self.total_livros);
+            self.total_livros = self.total_livros.saturating_add(1);
+            self.proximo_id = proximo_id;
+            for genero in &livro.generos {
+                self.indexar_genero(*genero, id_atual);
+            }
+            for autor in &livro.metadados.autores {
+                self.indexar_autor(*autor, id_atual);
+            }
+            self.indexar_titulo(&livro.titulo, "", id_atual);
+            self.env().emit_event(LivroAdicionado {
+                id: id_atual,
+                genero: livro.genero_principal(),
+                por: self.env().caller(),
+                titulo: livro.titulo,
+                generos: livro.generos,
+            });
+            self.incluir_exemplar(id_atual, exemplar_id, Conservacao::Novo)?;
+            Ok(id_atual) // Retorna o ID do livro adicionado
+        }
+
+        /// Retorna a lista de livros cadastrados.
+        ///
+        /// Percorre o catálogo inteiro; para catálogos grandes, use
+        /// `listar_livros_paginado`.
+        #[ink(message)]
+        pub fn listar_livros(&self) -> Vec<Livro> {
+            (0..self.total_livros)
+                .filter_map(|posicao| self.ids.get(posicao))
+                .filter_map(|id| self.ler_livro(id))
+                .collect()
+        }
+
+        /// Retorna um livro pelo ID.
+        #[ink(message)]
+        pub fn obter_livro(&self, id: u32) -> Option<Livro> {
+            self.ler_livro(id)
+        }
+
+        /// Retorna vários livros pelo ID, na mesma ordem de `ids`, com `None`
+        /// para os IDs inexistentes.
+        ///
+        /// Recusa com `ListaMuitoLonga` mais de `MAXIMO_POR_PAGINA` IDs.
+        #[ink(message)]
+        pub fn obter_livros(&self, ids: Vec<u32>) -> Result<Vec<Option<Livro>>> {
+            if ids.len() > MAXIMO_POR_PAGINA as usize {
+                return Err(Error::ListaMuitoLonga);
+            }
+            Ok(ids.into_iter().map(|id| self.ler_livro(id)).collect())
+        }
+
+        /// Indica se existe um livro com o ID informado.
+        #[ink(message)]
+        pub fn existe_livro(&self, id: u32) -> bool {
+            self.livros.contains(id)
+        }
+
+        /// Lista os livros em ordem crescente de ID, a partir de `desde_id`
+        /// (inclusive) ou do início do catálogo.
+        ///
+        /// `limite` é limitado a `MAXIMO_POR_PAGINA`. Retorna a página e o ID a
+        /// partir do qual buscar a próxima, ou `None` quando não há mais
+        /// livros. A página pode vir incompleta quando há muitos IDs removidos
+        /// no intervalo; nesse caso o cursor retornado continua a varredura.
+        #[ink(message)]
+        pub fn listar_livros_paginado(&self, desde_id: Option<u32>, limite: u32) -> (Vec<Livro>, Option<u32>) {
+            let limite = limite.min(MAXIMO_POR_PAGINA) as usize;
+            let inicio = desde_id.unwrap_or(1).max(1);
+            let fim = inicio.saturating_add(MAXIMO_IDS_POR_VARREDURA).min(self.proximo_id);
+            let mut livros = Vec::new();
+            let mut id = inicio;
+            while id < fim && livros.len() < limite {
+                if let Some(livro) = self.ler_livro(id) {
+                    livros.push(livro);
+                }
+                id += 1;
+            }
+            (livros, (id < self.proximo_id).then_some(id))
+        }
+
+        /// Busca livros cujo título contém todas as palavras de `termo`, uma
+        /// página por vez.
+        ///
+        /// O termo é normalizado como os títulos: minúsculas, sem acentos e
+        /// sem palavras como "o", "a" e "de". `cursor` é a posição inicial no
+        /// índice da palavra menos frequente do termo (0 na primeira página) e
+        /// `limite` é limitado a `MAXIMO_POR_PAGINA`. Retorna os IDs e o cursor
+        /// da próxima página, ou `None` quando não há mais livros. A página pode
+        /// vir incompleta quando poucos livros examinados combinam com o termo;
+        /// nesse caso o cursor retornado continua a varredura.
+        #[ink(message)]
+        pub fn buscar_por_titulo(&self, termo: String, cursor: u32, limite: u32) -> (Vec<u32>, Option<u32>) {
+            let palavras = palavras_chave(&termo);
+            let chaves = chaves_de_palavras(&palavras);
+            let conferir_no_titulo = palavras
+                .iter()
+                .any(|palavra| palavra.chars().count() > TAMANHO_MAXIMO_PALAVRA);
+            // Percorre a palavra menos frequente e confere as demais.
+            let Some(mais_rara) = chaves
+                .iter()
+                .min_by_key(|palavra| self.total_por_palavra.get(*palavra).unwrap_or(0))
+            else {
+                return (Vec::new(), None);
+            };
+            let total = self.total_por_palavra.get(mais_rara).unwrap_or(0);
+            self.varrer_indice(total, cursor, limite, |posicao| {
+                self.livros_por_palavra
+                    .get((mais_rara.clone(), posicao))
+                    .filter(|id| {
+                        chaves
+                            .iter()
+                            .all(|chave| self.posicoes_por_palavra.contains((chave.clone(), *id)))
+                    })
+                    .filter(|id| {
+                        // Palavras maiores que as indexadas são conferidas no título.
+                        !conferir_no_titulo
+                            || self.ler_livro(*id).is_some_and(|livro| {
+                                let palavras_do_titulo = palavras_chave(&livro.titulo);
+                                palavras.iter().all(|palavra| palavras_do_titulo.contains(palavra))
+                            })
+                    })
+            })
+        }
+
+        /// Busca livros com alguma palavra do título começando por `prefixo`,
+        /// uma página por vez.
+        ///
+        /// O prefixo é normalizado como os títulos e precisa ter ao menos
+        /// `TAMANHO_MINIMO_PREFIXO` caracteres. `cursor` e `limite` funcionam
+        /// como em `buscar_por_titulo`, sobre o índice do prefixo.
+        #[ink(message)]
+        pub fn buscar_por_prefixo(&self, prefixo: String, cursor: u32, limite: u32) -> (Vec<u32>, Option<u32>) {
+            let prefixo = normalizar_texto(prefixo.trim());
+            let tamanho = prefixo.chars().count();
+            if tamanho < TAMANHO_MINIMO_PREFIXO {
+                return (Vec::new(), None);
+            }
+            let chave: String = prefixo.chars().take(TAMANHO_MAXIMO_PREFIXO).collect();
+            let total = self.total_por_prefixo.get(&chave).unwrap_or(0);
+            self.varrer_indice(total, cursor, limite, |posicao| {
+                self.livros_por_prefixo.get((chave.clone(), posicao)).filter(|id| {
+                    // Prefixos maiores que os indexados são conferidos no título.
+                    tamanho <= TAMANHO_MAXIMO_PREFIXO
+                        || self.ler_livro(*id).is_some_and(|livro| {
+                            palavras_chave(&livro.titulo)
+                                .iter()
+                                .any(|palavra| palavra.starts_with(prefixo.as_str()))
+                        })
+                })
+            })
+        }
+
+        /// Busca um livro pelo ISBN-10 ou ISBN-13, com ou sem hífens.
+        #[ink(message)]
+        pub fn buscar_por_isbn(&self, isbn: String) -> Option<Livro> {
+            let isbn = Isbn::analisar(&isbn).ok()?;
+            self.isbns.get(isbn).and_then(|id| self.ler_livro(id))
+        }
+
+        /// Retorna a quantidade de livros cadastrados.
+        #[ink(message)]
+        pub fn total_livros(&self) -> u32 {
+            self.total_livros
+        }
+
+        /// Retorna a versão do layout de armazenamento.
+        #[ink(message)]
+        pub fn versao_storage(&self) -> u16 {
+            self.versao_storage
+        }
+
+        /// Troca o código do contrato, mantendo o armazenamento. Exclusivo do dono.
+        ///
+        /// Se o novo código usar outra `VERSAO_STORAGE`, as demais mensagens que
+        /// alteram o estado ficam bloqueadas até que `migrar` termine.
+        #[ink(message)]
+        pub fn atualizar_codigo(&mut self, code_hash: Hash) -> Result<()> {
+            self.garantir_dono()?;
+            ink::env::set_code_hash2::<Environment>(&code_hash).map_err(|_| Error::FalhaNaAtualizacao)?;
+            self.env().emit_event(CodigoAtualizado {
+                por: self.env().caller(),
+                code_hash,
+            });
+            Ok(())
+        }
+
+        /// Converte para o layout atual os livros gravados em uma versão
+        /// anterior, até `limite` IDs por chamada (no máximo
+        /// `MAXIMO_POR_PAGINA`). Exclusivo do dono.
+        ///
+        /// Enquanto a migração não termina, as consultas tratam como
+        /// inexistentes os livros ainda não convertidos. Retorna o próximo ID a
+        /// converter, ou `None` quando a migração termina e `versao_storage`
+        /// passa a ser `VERSAO_STORAGE`.
+        #[ink(message)]
+        pub fn migrar(&mut self, limite: u32) -> Result<Option<u32>> {
+            self.garantir_dono()?;
+            if self.versao_storage >= VERSAO_STORAGE {
+                return Ok(None);
+            }
+            let inicio = self.migracao_ate.get().unwrap_or(1).max(1);
+            let fim = inicio
+                .saturating_add(limite.clamp(1, MAXIMO_POR_PAGINA))
+                .min(self.proximo_id);
+            for id in inicio..fim {
+                if self.livros.contains(id) {
+                    self.migrar_livro(id)?;
+                }
+            }
+            if fim < self.proximo_id {
+                self.migracao_ate.set(&fim);
+                return Ok(Some(fim));
+            }
+            let versao_anterior = self.versao_storage;
+            self.versao_storage = VERSAO_STORAGE;
+            self.migracao_ate.set(&1);
+            self.env().emit_event(MigracaoConcluida {
+                versao_anterior,
+                versao_nova: VERSAO_STORAGE,
+            });
+            Ok(None)
+        }
+
+        /// Atualiza um livro existente pelo ID, alterando apenas os campos
+        /// informados em `alteracoes`.
+        #[ink(message)]
+        pub fn atualizar_livro(&mut self, id: u32, alteracoes: AtualizacaoLivro) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.garantir_papel(Papel::Bibliotecario)?;
+            let mut livro = self.livros.get(id).ok_or(Error::LivroNaoEncontrado)?;
+            let titulo_anterior = livro.titulo.clone();
+            let generos_anteriores = livro.generos.clone();
+            let autores_anteriores = livro.metadados.autores.clone();
+            let isbn_anterior = chave_isbn(&livro.metadados);
+            alteracoes.aplicar(&mut livro);
+            validar_titulo(&livro.titulo)?;
+            self.validar_generos(&livro.generos, &generos_anteriores)?;
+            validar_metadados(&livro.metadados)?;
+            self.validar_autores(&livro.metadados.autores)?;
+            let isbn = normalizar_isbn(&mut livro.metadados)?;
+            if isbn != isbn_anterior {
+                if let Some(isbn) = isbn {
+                    if self.isbns.contains(isbn) {
+                        return Err(Error::IsbnDuplicado);
+                    }
+                    self.isbns.insert(isbn, &id);
+                }
+                if let Some(isbn_anterior) = isbn_anterior {
+                    self.isbns.remove(isbn_anterior);
+                }
+            }
+            for genero in &generos_anteriores {
+                if !livro.generos.contains(genero) {
+                    self.desindexar_genero(*genero, id);
+                }
+            }
+            for genero in &livro.generos {
+                if !generos_anteriores.contains(genero) {
+                    self.indexar_genero(*genero, id);
+                }
+            }
+            for autor in &autores_anteriores {
+                if !livro.metadados.autores.contains(autor) {
+                    self.desindexar_autor(*autor, id);
+                }
+            }
+            for autor in &livro.metadados.autores {
+                if !autores_anteriores.contains(autor) {
+                    self.indexar_autor(*autor, id);
+                }
+            }
+            if livro.titulo != titulo_anterior {
+                self.desindexar_titulo(&titulo_anterior, &livro.titulo, id);
+                self.indexar_titulo(&livro.titulo, &titulo_anterior, id);
+            }
+            self.livros.insert(id, &livro);
+            self.env().emit_event(LivroAtualizado {
+                id,
+                genero: livro.genero_principal(),
+                por: self.env().caller(),
+                titulo_anterior,
+                titulo_novo: livro.titulo,
+                generos_anteriores,
+                generos_novos: livro.generos,
+            });
+            Ok(())
+        }
+
+        /// Remove um livro pelo ID, junto com todos os seus exemplares.
+        #[ink(message)]
+        pub fn remover_livro(&mut self, id: u32) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.garantir_papel(Papel::Bibliotecario)?;
+            let posicao = self.posicoes.get(id).ok_or(Error::LivroNaoEncontrado)?;
+            let exemplares = self.exemplares_por_livro.get(id).unwrap_or_default();
+            if exemplares.iter().any(|exemplar_id| self.emprestimos.contains(exemplar_id)) {
+                return Err(Error::EmprestimoAtivo);
+            }
+            for exemplar_id in exemplares {
+                self.exemplares.remove(exemplar_id);
+                self.retencoes.remove(exemplar_id);
+                self.transferir_custodia(exemplar_id, None);
+            }
+            self.exemplares_por_livro.remove(id);
+            self.reservas.remove(id);
+            // Move o último ID do índice para a posição liberada.
+            let ultima = self.total_livros.saturating_sub(1);
+            if posicao != ultima {
+                if let Some(ultimo_id) = self.ids.get(ultima) {
+                    self.ids.insert(posicao, &ultimo_id);
+                    self.posicoes.insert(ultimo_id, &posicao);
+                }
+            }
+            self.ids.remove(ultima);
+            self.posicoes.remove(id);
+            self.total_livros = ultima;
+            if let Some(livro) = self.livros.take(id) {
+                if let Some(isbn) = chave_isbn(&livro.metadados) {
+                    self.isbns.remove(isbn);
+                }
+                for genero in &livro.generos {
+                    self.desindexar_genero(*genero, id);
+                }
+                for autor in &livro.metadados.autores {
+                    self.desindexar_autor(*autor, id);
+                }
+                self.desindexar_titulo(&livro.titulo, "", id);
+                self.env().emit_event(LivroRemovido {
+                    id,
+                    genero: livro.genero_principal(),
+                    por: self.env().caller(),
+                    titulo: livro.titulo,
+                });
+            }
+            Ok(())
+        }
+
+        /// Cria um gênero, opcionalmente como subgênero de `pai`. Exige `Admin`.
+        ///
+        /// Retorna o ID do novo gênero.
+        #[ink(message)]
+        pub fn criar_genero(&mut self, nome: String, pai: Option<GeneroId>) -> Result<GeneroId> {
+            self.garantir_em_operacao()?;
+            self.garantir_papel(Papel::Admin)?;
+            validar_nome(&nome)?;
+            if self.ids_de_generos.contains(&nome) {
+                return Err(Error::GeneroDuplicado);
+            }
+            if let Some(pai) = pai {
+                let genero_pai = self.generos.get(pai).ok_or(Error::GeneroNaoEncontrado)?;
+                if genero_pai.descontinuado {
+                    return Err(Error::GeneroDescontinuado);
+                }
+            }
+            let id = self.proximo_genero_id;
+            self.proximo_genero_id = id.checked_add(1).ok_or(Error::IdEsgotado)?;
+            self.ids_de_generos.insert(&nome, &id);
+            self.generos.insert(
+                id,
+                &Genero {
+                    id,
+                    nome: nome.clone(),
+                    pai,
+                    descontinuado: false,
+                },
+            );
+            self.env().emit_event(GeneroCriado { id, pai, nome });
+            Ok(id)
+        }
+
+        /// Renomeia um gênero. Exige `Admin`.
+        #[ink(message)]
+        pub fn renomear_genero(&mut self, id: GeneroId, nome: String) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.garantir_papel(Papel::Admin)?;
+            validar_nome(&nome)?;
+            let mut genero = self.generos.get(id).ok_or(Error::GeneroNaoEncontrado)?;
+            if self.ids_de_generos.contains(&nome) {
+                return Err(Error::GeneroDuplicado);
+            }
+            self.ids_de_generos.remove(&genero.nome);
+            self.ids_de_generos.insert(&nome, &id);
+            let nome_anterior = core::mem::replace(&mut genero.nome, nome);
+            self.generos.insert(id, &genero);
+            self.env().emit_event(GeneroRenomeado {
+                id,
+                nome_anterior,
+                nome_novo: genero.nome,
+            });
+            Ok(())
+        }
+
+        /// Descontinua um gênero, impedindo que seja atribuído a outros livros.
+        /// Exige `Admin`.
+        #[ink(message)]
+        pub fn descontinuar_genero(&mut self, id: GeneroId) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.garantir_papel(Papel::Admin)?;
+            let mut genero = self.generos.get(id).ok_or(Error::GeneroNaoEncontrado)?;
+            if genero.descontinuado {
+                return Ok(());
+            }
+            genero.descontinuado = true;
+            self.generos.insert(id, &genero);
+            self.env().emit_event(GeneroDescontinuado { id });
+            Ok(())
+        }
+
+        /// Retorna um gênero pelo ID.
+        #[ink(message)]
+        pub fn genero(&self, id: GeneroId) -> Option<Genero> {
+            self.generos.get(id)
+        }
+
+        /// Retorna todos os gêneros cadastrados, inclusive os descontinuados.
+        #[ink(message)]
+        pub fn listar_generos(&self) -> Vec<Genero> {
+            (0..self.proximo_genero_id)
+                .filter_map(|id| self.generos.get(id))
+                .collect()
+        }
+
+        /// Lista os livros de um gênero, uma página por vez.
+        ///
+        /// `cursor` é a posição inicial no índice do gênero (0 na primeira
+        /// página) e `limite` é limitado a `MAXIMO_POR_PAGINA`. Retorna a
+        /// página e o cursor da próxima, ou `None` na última página.
+        ///
+        /// As páginas não formam um retrato consistente do gênero: remover um
+        /// livro do índice move o último livro para a posição liberada, e esse
+        /// livro é pulado se a posição já tiver sido percorrida. Para listar o
+        /// catálogo sem lacunas, use `listar_livros_paginado`, ordenado por ID.
+        #[ink(message)]
+        pub fn listar_por_genero(&self, genero: GeneroId, cursor: u32, limite: u32) -> (Vec<Livro>, Option<u32>) {
+            let total = self.total_por_genero.get(genero).unwrap_or(0);
+            let fim = cursor.saturating_add(limite.min(MAXIMO_POR_PAGINA)).min(total);
+            let livros = (cursor..fim)
+                .filter_map(|posicao| self.livros_por_genero.get((genero, posicao)))
+                .filter_map(|id| self.ler_livro(id))
+                .collect();
+            (livros, (fim < total).then_some(fim))
+        }
+
+        /// Retorna a quantidade de livros de cada gênero cadastrado.
+        #[ink(message)]
+        pub fn contar_por_genero(&self) -> Vec<(GeneroId, u32)> {
+            (0..self.proximo_genero_id)
+                .filter(|id| self.generos.contains(id))
+                .map(|id| (id, self.total_por_genero.get(id).unwrap_or(0)))
+                .collect()
+        }
+
+        /// Cadastra um autor. Exige `Bibliotecario`.
+        ///
+        /// Retorna o ID do novo autor.
+        #[ink(message)]
+        pub fn adicionar_autor(
+            &mut self,
+            nome: String,
+            ano_nascimento: Option<u16>,
+            ano_falecimento: Option<u16>,
+            nacionalidade: Option<String>,
+        ) -> Result<AutorId> {
+            self.garantir_em_operacao()?;
+            self.garantir_papel(Papel::Bibliotecario)?;
+            let id = self.proximo_autor_id;
+            let autor = Autor {
+                id,
+                nome,
+                ano_nascimento,
+                ano_falecimento,
+                nacionalidade,
+            };
+            validar_autor(&autor)?;
+            self.proximo_autor_id = id.checked_add(1).ok_or(Error::IdEsgotado)?;
+            self.autores.insert(id, &autor);
+            self.env().emit_event(AutorAdicionado {
+                id,
+                por: self.env().caller(),
+                nome: autor.nome,
+            });
+            Ok(id)
+        }
+
+        /// Substitui os dados de um autor. Exige `Bibliotecario`.
+        #[ink(message)]
+        pub fn atualizar_autor(
+            &mut self,
+            id: AutorId,
+            nome: String,
+            ano_nascimento: Option<u16>,
+            ano_falecimento: Option<u16>,
+            nacionalidade: Option<String>,
+        ) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.garantir_papel(Papel::Bibliotecario)?;
+            if !self.autores.contains(id) {
+                return Err(Error::AutorNaoEncontrado);
+            }
+            let autor = Autor {
+                id,
+                nome,
+                ano_nascimento,
+                ano_falecimento,
+                nacionalidade,
+            };
+            validar_autor(&autor)?;
+            self.autores.insert(id, &autor);
+            self.env().emit_event(AutorAtualizado {
+                id,
+                por: self.env().caller(),
+                nome: autor.nome,
+            });
+            Ok(())
+        }
+
+        /// Remove um autor que não é referenciado por nenhum livro. Exige
+        /// `Bibliotecario`.
+        #[ink(message)]
+        pub fn remover_autor(&mut self, id: AutorId) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.garantir_papel(Papel::Bibliotecario)?;
+            if self.total_por_autor.get(id).unwrap_or(0) > 0 {
+                return Err(Error::AutorReferenciado);
+            }
+            let autor = self.autores.take(id).ok_or(Error::AutorNaoEncontrado)?;
+            self.env().emit_event(AutorRemovido {
+                id,
+                por: self.env().caller(),
+                nome: autor.nome,
+            });
+            Ok(())
+        }
+
+        /// Retorna um autor pelo ID.
+        #[ink(message)]
+        pub fn autor(&self, id: AutorId) -> Option<Autor> {
+            self.autores.get(id)
+        }
+
+        /// Lista os livros de um autor, uma página por vez, como em
+        /// `listar_por_genero`. Como lá, remover livros entre uma página e
+        /// outra pode fazer um livro ser pulado.
+        #[ink(message)]
+        pub fn listar_por_autor(&self, autor_id: AutorId, cursor: u32, limite: u32) -> (Vec<Livro>, Option<u32>) {
+            let total = self.total_por_autor.get(autor_id).unwrap_or(0);
+            let fim = cursor.saturating_add(limite.min(MAXIMO_POR_PAGINA)).min(total);
+            let livros = (cursor..fim)
+                .filter_map(|posicao| self.livros_por_autor.get((autor_id, posicao)))
+                .filter_map(|id| self.ler_livro(id))
+                .collect();
+            (livros, (fim < total).then_some(fim))
+        }
+
+        /// Adiciona um exemplar a um título existente.
+        ///
+        /// Retorna o ID do novo exemplar.
+        #[ink(message)]
+        pub fn adicionar_exemplar(&mut self, livro_id: u32, conservacao: Conservacao) -> Result<u32> {
+            self.garantir_em_operacao()?;
+            self.garantir_papel(Papel::Bibliotecario)?;
+            if !self.livros.contains(livro_id) {
+                return Err(Error::LivroNaoEncontrado);
+            }
+            let exemplar_id = self.reservar_id_exemplar()?;
+            self.incluir_exemplar(livro_id, exemplar_id, conservacao)?;
+            Ok(exemplar_id)
+        }
+
+        /// Retira um exemplar do acervo.
+        #[ink(message)]
+        pub fn retirar_exemplar(&mut self, exemplar_id: u32) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.garantir_papel(Papel::Bibliotecario)?;
+            let exemplar = self.exemplares.get(exemplar_id).ok_or(Error::ExemplarNaoEncontrado)?;
+            if self.emprestimos.contains(exemplar_id) {
+                return Err(Error::EmprestimoAtivo);
+            }
+            if self.retencoes.contains(exemplar_id) {
+                return Err(Error::ExemplarReservado);
+            }
+            let mut exemplares = self.exemplares_por_livro.get(exemplar.livro_id).unwrap_or_default();
+            exemplares.retain(|id| *id != exemplar_id);
+            self.exemplares_por_livro.insert(exemplar.livro_id, &exemplares);
+            self.exemplares.remove(exemplar_id);
+            self.transferir_custodia(exemplar_id, None);
+            self.env().emit_event(ExemplarRetirado {
+                livro_id: exemplar.livro_id,
+                exemplar_id,
+            });
+            Ok(())
+        }
+
+        /// Atualiza o estado de conservação de um exemplar.
+        #[ink(message)]
+        pub fn definir_conservacao(&mut self, exemplar_id: u32, conservacao: Conservacao) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.garantir_papel(Papel::Bibliotecario)?;
+            let mut exemplar = self.exemplares.get(exemplar_id).ok_or(Error::ExemplarNaoEncontrado)?;
+            exemplar.conservacao = conservacao;
+            self.exemplares.insert(exemplar_id, &exemplar);
+            Ok(())
+        }
+
+        /// Retorna um exemplar pelo ID.
+        #[ink(message)]
+        pub fn exemplar(&self, exemplar_id: u32) -> Option<Exemplar> {
+            self.exemplares.get(exemplar_id)
+        }
+
+        /// Retorna todos os exemplares de um título.
+        #[ink(message)]
+        pub fn exemplares(&self, livro_id: u32) -> Vec<Exemplar> {
+            self.exemplares_por_livro
+                .get(livro_id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|exemplar_id| self.exemplares.get(exemplar_id))
+                .collect()
+        }
+
+        /// Retorna os exemplares de um título disponíveis para empréstimo.
+        #[ink(message)]
+        pub fn exemplares_disponiveis(&self, livro_id: u32) -> Vec<Exemplar> {
+            self.exemplares(livro_id)
+                .into_iter()
+                .filter(|exemplar| exemplar.status == StatusExemplar::Disponivel)
+                .collect()
+        }
+
+        /// Registra o chamador como membro do nível `Basico` e concede o papel `Membro`.
+        ///
+        /// Empréstimos e reservas exigem, além do registro, uma assinatura válida
+        /// e o papel `Membro`; revogar o papel suspende esses direitos.
+        #[ink(message)]
+        pub fn registrar_membro(&mut self, nome: String) -> Result<()> {
+            self.garantir_em_operacao()?;
+            validar_nome(&nome)?;
+            let conta = self.env().caller();
+            if self.membros.contains(conta) {
+                return Err(Error::MembroJaRegistrado);
+            }
+            self.membros.insert(
+                conta,
+                &Membro {
+                    nome: nome.clone(),
+                    desde: self.env().block_timestamp(),
+                    nivel: NivelMembro::Basico,
+                    status: StatusMembro::Ativo,
+                    expira_em: 0,
+                },
+            );
+            self.conceder(Papel::Membro, conta);
+            self.env().emit_event(MembroRegistrado { conta, nome });
+            Ok(())
+        }
+
+        /// Atualiza o nome ou o nível de um membro.
+        ///
+        /// O próprio membro pode alterar o nome; alterar o nível ou o cadastro
+        /// de outra conta exige `Bibliotecario`.
+        #[ink(message)]
+        pub fn atualizar_membro(
+            &mut self,
+            conta: AccountId,
+            nome: Option<String>,
+            nivel: Option<NivelMembro>,
+        ) -> Result<()> {
+            self.garantir_em_operacao()?;
+            if self.env().caller() != conta || nivel.is_some() {
+                self.garantir_papel(Papel::Bibliotecario)?;
+            }
+            let mut membro = self.membros.get(conta).ok_or(Error::MembroNaoRegistrado)?;
+            if let Some(nome) = nome {
+                validar_nome(&nome)?;
+                membro.nome = nome;
+            }
+            if let Some(nivel) = nivel {
+                membro.nivel = nivel;
+            }
+            self.membros.insert(conta, &membro);
+            self.env().emit_event(MembroAtualizado {
+                conta,
+                por: self.env().caller(),
+                nome: membro.nome,
+                nivel: membro.nivel,
+            });
+            Ok(())
+        }
+
+        /// Suspende um membro, impedindo novos empréstimos e reservas.
+        #[ink(message)]
+        pub fn suspender_membro(&mut self, conta: AccountId) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.alterar_status_do_membro(conta, StatusMembro::Suspenso)
+        }
+
+        /// Reativa um membro suspenso.
+        #[ink(message)]
+        pub fn reativar_membro(&mut self, conta: AccountId) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.alterar_status_do_membro(conta, StatusMembro::Ativo)
+        }
+
+        /// Compra um período de assinatura de `nivel` para o chamador.
+        ///
+        /// O nível muda na hora. Se a assinatura ainda estiver válida, o novo
+        /// período começa no fim da atual, sem perder o tempo já pago. O valor
+        /// transferido deve ser exatamente o preço do nível.
+        /// Retorna o novo fim da assinatura.
+        #[ink(message, payable)]
+        pub fn comprar_assinatura(&mut self, nivel: NivelMembro) -> Result<Timestamp> {
+            self.garantir_em_operacao()?;
+            self.comprar_assinatura_em(nivel, Moeda::Nativa)
+        }
+
+        /// Como `comprar_assinatura`, mas paga no token de pagamento.
+        #[ink(message)]
+        pub fn comprar_assinatura_com_token(&mut self, nivel: NivelMembro) -> Result<Timestamp> {
+            self.garantir_em_operacao()?;
+            let moeda = Moeda::Token(self.token_de_pagamento()?);
+            self.comprar_assinatura_em(nivel, moeda)
+        }
+
+        /// Estende a assinatura do chamador por mais um período do nível atual.
+        ///
+        /// Se a assinatura ainda estiver válida, o novo período começa no fim da
+        /// atual. Retorna o novo fim da assinatura.
+        #[ink(message, payable)]
+        pub fn renovar_assinatura(&mut self) -> Result<Timestamp> {
+            self.garantir_em_operacao()?;
+            self.renovar_assinatura_em(Moeda::Nativa)
+        }
+
+        /// Como `renovar_assinatura`, mas paga no token de pagamento.
+        #[ink(message)]
+        pub fn renovar_assinatura_com_token(&mut self) -> Result<Timestamp> {
+            self.garantir_em_operacao()?;
+            let moeda = Moeda::Token(self.token_de_pagamento()?);
+            self.renovar_assinatura_em(moeda)
+        }
+
+        /// Retorna o cadastro de um membro.
+        ///
+        /// Um membro ativo cuja assinatura venceu é retornado como `Expirado`.
+        #[ink(message)]
+        pub fn membro(&self, conta: AccountId) -> Option<Membro> {
+            self.membros.get(conta).map(|mut membro| {
+                if membro.status == StatusMembro::Ativo && membro.expira_em <= self.env().block_timestamp() {
+                    membro.status = StatusMembro::Expirado;
+                }
+                membro
+            })
+        }
+
+        /// Retorna a quantidade de empréstimos em andamento de um membro.
+        #[ink(message)]
+        pub fn emprestimos_ativos(&self, conta: AccountId) -> u32 {
+            self.emprestimos_ativos.get(conta).unwrap_or(0)
+        }
+
+        /// Define a caução exigida para emprestar um título. Zero dispensa a caução.
+        #[ink(message)]
+        pub fn definir_caucao(&mut self, livro_id: u32, valor: Balance) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.garantir_papel(Papel::Bibliotecario)?;
+            if !self.livros.contains(livro_id) {
+                return Err(Error::LivroNaoEncontrado);
+            }
+            if valor == 0 {
+                self.caucoes.remove(livro_id);
+            } else {
+                self.caucoes.insert(livro_id, &valor);
+            }
+            Ok(())
+        }
+
+        /// Retorna a caução exigida para emprestar um título.
+        #[ink(message)]
+        pub fn caucao(&self, livro_id: u32) -> Balance {
+            self.caucoes.get(livro_id).unwrap_or(0)
+        }
+
+        /// Empresta ao chamador um exemplar do título.
+        ///
+        /// Se houver um exemplar retido para o chamador, ele é o emprestado;
+        /// caso contrário, é escolhido um exemplar disponível. O valor
+        /// transferido deve ser exatamente a caução do título.
+        /// Retorna o ID do exemplar emprestado.
+        #[ink(message, payable)]
+        pub fn emprestar_livro(&mut self, id: u32) -> Result<u32> {
+            self.garantir_em_operacao()?;
+            self.emprestar(id, Moeda::Nativa)
+        }
+
+        /// Como `emprestar_livro`, mas paga a caução no token de pagamento,
+        /// que é também a moeda do reembolso.
+        #[ink(message)]
+        pub fn emprestar_livro_com_token(&mut self, id: u32) -> Result<u32> {
+            self.garantir_em_operacao()?;
+            let moeda = Moeda::Token(self.token_de_pagamento()?);
+            self.emprestar(id, moeda)
+        }
+
+        fn emprestar(&mut self, id: u32, moeda: Moeda) -> Result<u32> {
+            let tomador = self.env().caller();
+            let membro = self.garantir_membro_ativo(tomador)?;
+            if !self.livros.contains(id) {
+                return Err(Error::LivroNaoEncontrado);
+            }
+            let caucao = self.caucao(id);
+            if moeda == Moeda::Nativa && self.env().transferred_value() != caucao {
+                return Err(Error::ValorInvalido);
+            }
+            if self.multa_pendente(tomador) > self.configuracao.limite_multa {
+                return Err(Error::MultasPendentes);
+            }
+            let regras = self.configuracao.regras(membro.nivel);
+            let ativos = self.emprestimos_ativos(tomador);
+            if ativos >= regras.maximo_emprestimos {
+                return Err(Error::LimiteDeEmprestimos);
+            }
+            self.processar_retencoes(id);
+            let mut exemplar = match self.exemplar_retido_para(id, tomador) {
+                Some(exemplar) => {
+                    self.retencoes.remove(exemplar.id);
+                    exemplar
+                }
+                None => self
+                    .exemplares_disponiveis(id)
+                    .into_iter()
+                    .next()
+                    .ok_or(Error::LivroIndisponivel)?,
+            };
+            if let Moeda::Token(token) = moeda {
+                self.receber_em_token(token, tomador, caucao)?;
+            }
+            let inicio = self.env().block_timestamp();
+            let vencimento = inicio.saturating_add(regras.prazo_emprestimo);
+            self.emprestimos_ativos.insert(tomador, &ativos.saturating_add(1));
+            self.emprestimos.insert(
+                exemplar.id,
+                &Emprestimo {
+                    tomador,
+                    inicio,
+                    vencimento,
+                    renovacoes: 0,
+                    caucao,
+                    dano: 0,
+                    moeda_da_caucao: moeda,
+                },
+            );
+            exemplar.status = StatusExemplar::Emprestado;
+            self.exemplares.insert(exemplar.id, &exemplar);
+            self.transferir_custodia(exemplar.id, Some(tomador));
+            self.env().emit_event(LivroEmprestado {
+                id,
+                exemplar_id: exemplar.id,
+                tomador,
+                vencimento,
+            });
+            Ok(exemplar.id)
+        }
+
+        /// Registra a devolução de um exemplar emprestado.
+        ///
+        /// Pode ser chamada pelo tomador ou por um bibliotecário. A multa por
+        /// atraso, os danos registrados e as multas pendentes do tomador são
+        /// descontados da caução, e o restante é reembolsado. Se o reembolso
+        /// falhar, a devolução vale mesmo assim e o valor fica disponível para
+        /// o tomador em `sacar_reembolso`.
+        #[ink(message)]
+        pub fn devolver_livro(&mut self, exemplar_id: u32) -> Result<()> {
+            self.garantir_em_operacao()?;
+            let emprestimo = self.emprestimos.get(exemplar_id).ok_or(Error::LivroNaoEmprestado)?;
+            if self.env().caller() != emprestimo.tomador {
+                self.garantir_papel(Papel::Bibliotecario)?;
+            }
+            let mut exemplar = self.exemplares.get(exemplar_id).ok_or(Error::ExemplarNaoEncontrado)?;
+            self.encerrar_emprestimo(exemplar_id, emprestimo.tomador);
+            self.transferir_custodia(exemplar_id, Some(self.env().account_id()));
+            self.env().emit_event(LivroDevolvido {
+                id: exemplar.livro_id,
+                exemplar_id,
+                tomador: emprestimo.tomador,
+            });
+            let agora = self.env().block_timestamp();
+            let duracao = agora.saturating_sub(emprestimo.inicio);
+            if agora <= emprestimo.vencimento && duracao >= self.configuracao.duracao_minima_para_pontos {
+                self.emitir_pontos(emprestimo.tomador, self.configuracao.pontos_por_devolucao);
+            }
+            let multa = self.calcular_multa(&emprestimo);
+            if multa > 0 {
+                self.env().emit_event(MultaAplicada {
+                    membro: emprestimo.tomador,
+                    exemplar_id,
+                    valor: multa,
+                });
+            }
+            let devido = self
+                .multa_pendente(emprestimo.tomador)
+                .saturating_add(multa)
+                .saturating_add(emprestimo.dano);
+            let retido = devido.min(emprestimo.caucao);
+            let reembolso = emprestimo.caucao - retido;
+            self.definir_multa(emprestimo.tomador, devido - retido);
+            self.creditar(emprestimo.moeda_da_caucao, retido);
+            self.processar_retencoes(exemplar.livro_id);
+            self.liberar_exemplar(&mut exemplar);
+            self.exemplares.insert(exemplar_id, &exemplar);
+            if emprestimo.caucao > 0 {
+                let moeda = emprestimo.moeda_da_caucao;
+                if self.enviar(moeda, emprestimo.tomador, reembolso).is_err() {
+                    let pendente = self.reembolso_pendente(emprestimo.tomador, moeda).saturating_add(reembolso);
+                    self.reembolsos_pendentes.insert((emprestimo.tomador, moeda), &pendente);
+                    self.env().emit_event(ReembolsoPendente {
+                        conta: emprestimo.tomador,
+                        moeda,
+                        valor: reembolso,
+                    });
+                }
+                self.env().emit_event(CaucaoDevolvida {
+                    exemplar_id,
+                    tomador: emprestimo.tomador,
+                    reembolso,
+                    retido,
+                });
+            }
+            Ok(())
+        }
+
+        /// Retorna o reembolso de caução pendente de `conta` em `moeda`.
+        #[ink(message)]
+        pub fn reembolso_pendente(&self, conta: AccountId, moeda: Moeda) -> Balance {
+            self.reembolsos_pendentes.get((conta, moeda)).unwrap_or(0)
+        }
+
+        /// Envia ao chamador o reembolso de caução pendente em `moeda`.
+        ///
+        /// Retorna o valor enviado.
+        #[ink(message)]
+        pub fn sacar_reembolso(&mut self, moeda: Moeda) -> Result<Balance> {
+            self.garantir_em_operacao()?;
+            let conta = self.env().caller();
+            let valor = self.reembolso_pendente(conta, moeda);
+            if valor == 0 {
+                return Err(Error::ValorInvalido);
+            }
+            self.reembolsos_pendentes.remove((conta, moeda));
+            self.enviar(moeda, conta, valor)?;
+            Ok(valor)
+        }
+
+        /// Registra um dano a um exemplar emprestado, cobrado do tomador na devolução.
+        #[ink(message)]
+        pub fn registrar_dano(&mut self, exemplar_id: u32, valor: Balance) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.garantir_papel(Papel::Bibliotecario)?;
+            let mut emprestimo = self.emprestimos.get(exemplar_id).ok_or(Error::LivroNaoEmprestado)?;
+            if valor == 0 {
+                return Err(Error::ValorInvalido);
+            }
+            emprestimo.dano = emprestimo.dano.saturating_add(valor);
+            self.emprestimos.insert(exemplar_id, &emprestimo);
+            self.env().emit_event(DanoRegistrado {
+                exemplar_id,
+                tomador: emprestimo.tomador,
+                valor,
+            });
+            Ok(())
+        }
+
+        /// Dá como perdido um exemplar emprestado, encerrando o empréstimo.
+        ///
+        /// A caução é retida integralmente pela biblioteca e o token do
+        /// exemplar é queimado.
+        #[ink(message)]
+        pub fn marcar_perdido(&mut self, exemplar_id: u32) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.garantir_papel(Papel::Bibliotecario)?;
+            let emprestimo = self.emprestimos.get(exemplar_id).ok_or(Error::LivroNaoEmprestado)?;
+            let mut exemplar = self.exemplares.get(exemplar_id).ok_or(Error::ExemplarNaoEncontrado)?;
+            self.encerrar_emprestimo(exemplar_id, emprestimo.tomador);
+            exemplar.status = StatusExemplar::Perdido;
+            self.exemplares.insert(exemplar_id, &exemplar);
+            self.transferir_custodia(exemplar_id, None);
+            self.creditar(emprestimo.moeda_da_caucao, emprestimo.caucao);
+            self.env().emit_event(ExemplarPerdido {
+                livro_id: exemplar.livro_id,
+                exemplar_id,
+                tomador: emprestimo.tomador,
+                caucao_retida: emprestimo.caucao,
+            });
+            Ok(())
+        }
+
+        /// Renova o empréstimo de um exemplar, estendendo o vencimento em
+        /// `prazo_renovacao`.
+        ///
+        /// Pode ser chamada pelo tomador ou por um bibliotecário. É recusada se
+        /// houver membros na fila de reservas do título.
+        /// Retorna o novo vencimento.
+        #[ink(message)]
+        pub fn renovar_emprestimo(&mut self, exemplar_id: u32) -> Result<Timestamp> {
+            self.garantir_em_operacao()?;
+            let mut emprestimo = self.emprestimos.get(exemplar_id).ok_or(Error::LivroNaoEmprestado)?;
+            if self.env().caller() != emprestimo.tomador {
+                self.garantir_papel(Papel::Bibliotecario)?;
+            }
+            self.garantir_membro_ativo(emprestimo.tomador)?;
+            let exemplar = self.exemplares.get(exemplar_id).ok_or(Error::ExemplarNaoEncontrado)?;
+            self.processar_retencoes(exemplar.livro_id);
+            if !self.reservas.get(exemplar.livro_id).unwrap_or_default().is_empty() {
+                return Err(Error::ReservaPendente);
+            }
+            if emprestimo.renovacoes >= self.configuracao.maximo_renovacoes {
+                return Err(Error::LimiteDeRenovacoes);
+            }
+            emprestimo.renovacoes = emprestimo.renovacoes.saturating_add(1);
+            emprestimo.vencimento = emprestimo
+                .vencimento
+                .saturating_add(self.configuracao.prazo_renovacao);
+            self.emprestimos.insert(exemplar_id, &emprestimo);
+            self.env().emit_event(EmprestimoRenovado {
+                exemplar_id,
+                tomador: emprestimo.tomador,
+                vencimento: emprestimo.vencimento,
+                renovacoes: emprestimo.renovacoes,
+            });
+            Ok(emprestimo.vencimento)
+        }
+
+        /// Retorna o empréstimo em andamento de um exemplar, se houver.
+        #[ink(message)]
+        pub fn emprestimo(&self, exemplar_id: u32) -> Option<Emprestimo> {
+            self.emprestimos.get(exemplar_id)
+        }
+
+        /// Retorna a multa que seria cobrada se o exemplar fosse devolvido agora.
+        #[ink(message)]
+        pub fn multa_do_emprestimo(&self, exemplar_id: u32) -> Balance {
+            self.emprestimos
+                .get(exemplar_id)
+                .map_or(0, |emprestimo| self.calcular_multa(&emprestimo))
+        }
+
+        /// Retorna as multas pendentes de `membro`.
+        #[ink(message)]
+        pub fn multa_pendente(&self, membro: AccountId) -> Balance {
+            self.multas.get(membro).unwrap_or(0)
+        }
+
+        /// Paga multas pendentes do chamador com o saldo nativo transferido.
+        ///
+        /// O valor não pode exceder o total devido. Retorna o saldo restante.
+        #[ink(message, payable)]
+        pub fn pagar_multa(&mut self) -> Result<Balance> {
+            self.garantir_em_operacao()?;
+            self.pagar_multa_em(Moeda::Nativa, self.env().transferred_value())
+        }
+
+        /// Paga `valor` das multas pendentes do chamador no token de pagamento.
+        ///
+        /// O valor não pode exceder o total devido. Retorna o saldo restante.
+        #[ink(message)]
+        pub fn pagar_multa_com_token(&mut self, valor: Balance) -> Result<Balance> {
+            self.garantir_em_operacao()?;
+            let moeda = Moeda::Token(self.token_de_pagamento()?);
+            self.pagar_multa_em(moeda, valor)
+        }
+
+        fn pagar_multa_em(&mut self, moeda: Moeda, valor: Balance) -> Result<Balance> {
+            let membro = self.env().caller();
+            let devido = self.multa_pendente(membro);
+            if valor == 0 || valor > devido {
+                return Err(Error::ValorInvalido);
+            }
+            self.receber(moeda, membro, valor)?;
+            let restante = devido - valor;
+            self.definir_multa(membro, restante);
+            self.env().emit_event(MultaPaga {
+                membro,
+                valor,
+                restante,
+            });
+            Ok(restante)
+        }
+
+        /// Paga multas pendentes do chamador com pontos de leitura, cada um
+        /// valendo `valor_do_ponto`.
+        ///
+        /// Não podem ser gastos mais pontos do que o necessário para quitar o
+        /// total devido. Retorna o saldo restante.
+        #[ink(message)]
+        pub fn pagar_multa_com_pontos(&mut self, pontos: Balance) -> Result<Balance> {
+            self.garantir_em_operacao()?;
+            let membro = self.env().caller();
+            let devido = self.multa_pendente(membro);
+            let valor_do_ponto = self.configuracao.valor_do_ponto;
+            if pontos == 0 || valor_do_ponto == 0 || pontos > devido.div_ceil(valor_do_ponto) {
+                return Err(Error::ValorInvalido);
+            }
+            self.queimar_pontos(membro, pontos)?;
+            let valor = pontos.saturating_mul(valor_do_ponto).min(devido);
+            let restante = devido - valor;
+            self.definir_multa(membro, restante);
+            self.env().emit_event(MultaPaga {
+                membro,
+                valor,
+                restante,
+            });
+            Ok(restante)
+        }
+
+        /// Retorna o saldo arrecadado disponível para saque.
+        #[ink(message)]
+        pub fn fundos_disponiveis(&self) -> Balance {
+            self.fundos_disponiveis
+        }
+
+        /// Retorna o saldo arrecadado em `token` disponível para saque.
+        ///
+        /// Os fundos são separados por token, inclusive os recebidos em tokens
+        /// que deixaram de ser o token de pagamento.
+        #[ink(message)]
+        pub fn fundos_em_token(&self, token: AccountId) -> Balance {
+            self.fundos_em_token.get(token).unwrap_or(0)
+        }
+
+        /// Transfere fundos arrecadados para `destino`. Exclusivo do dono.
+        #[ink(message)]
+        pub fn sacar_fundos(&mut self, valor: Balance, destino: AccountId) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.sacar(Moeda::Nativa, valor, destino)
+        }
+
+        /// Transfere fundos arrecadados em `token` para `destino`. Exclusivo
+        /// do dono.
+        #[ink(message)]
+        pub fn sacar_fundos_em_token(&mut self, token: AccountId, valor: Balance, destino: AccountId) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.sacar(Moeda::Token(token), valor, destino)
+        }
+
+        fn sacar(&mut self, moeda: Moeda, valor: Balance, destino: AccountId) -> Result<()> {
+            self.garantir_dono()?;
+            if valor == 0 {
+                return Err(Error::ValorInvalido);
+            }
+            let fundos = match moeda {
+                Moeda::Nativa => self.fundos_disponiveis,
+                Moeda::Token(token) => self.fundos_em_token(token),
+            };
+            if valor > fundos {
+                return Err(Error::SaldoInsuficiente);
+            }
+            self.enviar(moeda, destino, valor)?;
+            let restante = fundos - valor;
+            match moeda {
+                Moeda::Nativa => self.fundos_disponiveis = restante,
+                Moeda::Token(token) => {
+                    self.fundos_em_token.insert(token, &restante);
+                }
+            }
+            self.env().emit_event(FundosSacados {
+                por: self.env().caller(),
+                destino,
+                moeda,
+                valor,
+                restante,
+            });
+            Ok(())
+        }
+
+        /// Coloca o chamador no fim da fila de reservas de um título sem
+        /// exemplares disponíveis.
+        ///
+        /// O valor transferido deve ser exatamente a `taxa_reserva`, que não é
+        /// devolvida se a reserva for cancelada.
+        /// Retorna a posição do chamador na fila, começando em 1.
+        #[ink(message, payable)]
+        pub fn reservar_livro(&mut self, id: u32) -> Result<u32> {
+            self.garantir_em_operacao()?;
+            let taxa = self.configuracao.taxa_reserva;
+            if self.env().transferred_value() != taxa {
+                return Err(Error::ValorInvalido);
+            }
+            let posicao = self.entrar_na_fila(id)?;
+            self.fundos_disponiveis = self.fundos_disponiveis.saturating_add(taxa);
+            Ok(posicao)
+        }
+
+        /// Como `reservar_livro`, mas paga a `taxa_reserva` com pontos de
+        /// leitura, arredondando para cima a quantidade de pontos.
+        #[ink(message)]
+        pub fn reservar_livro_com_pontos(&mut self, id: u32) -> Result<u32> {
+            self.garantir_em_operacao()?;
+            let membro = self.env().caller();
+            let pontos = match self.configuracao.valor_do_ponto {
+                0 => return Err(Error::ValorInvalido),
+                valor_do_ponto => self.configuracao.taxa_reserva.div_ceil(valor_do_ponto),
+            };
+            if PSP22::balance_of(self, membro) < pontos {
+                return Err(Error::PontosInsuficientes);
+            }
+            let posicao = self.entrar_na_fila(id)?;
+            self.queimar_pontos(membro, pontos)?;
+            Ok(posicao)
+        }
+
+        /// Inclui o chamador na fila de reservas de um título.
+        fn entrar_na_fila(&mut self, id: u32) -> Result<u32> {
+            self.garantir_membro_ativo(self.env().caller())?;
+            if !self.livros.contains(id) {
+                return Err(Error::LivroNaoEncontrado);
+            }
+            self.processar_retencoes(id);
+            if !self.exemplares_disponiveis(id).is_empty() {
+                return Err(Error::ExemplarDisponivel);
+            }
+            let membro = self.env().caller();
+            if self.exemplar_retido_para(id, membro).is_some() {
+                return Err(Error::ReservaExistente);
+            }
+            let mut fila = self.reservas.get(id).unwrap_or_default();
+            if fila.contains(&membro) {
+                return Err(Error::ReservaExistente);
+            }
+            if fila.len() >= MAXIMO_RESERVAS_POR_LIVRO {
+                return Err(Error::FilaCheia);
+            }
+            fila.push(membro);
+            let posicao = fila.len() as u32;
+            self.reservas.insert(id, &fila);
+            self.env().emit_event(ReservaRealizada {
+                livro_id: id,
+                membro,
+                posicao,
+            });
+            Ok(posicao)
+        }
+
+        /// Retira o chamador da fila de reservas de um título.
+        ///
+        /// Se houver um exemplar retido para o chamador, ele passa ao próximo da fila.
+        #[ink(message)]
+        pub fn cancelar_reserva(&mut self, id: u32) -> Result<()> {
+            self.garantir_em_operacao()?;
+            self.processar_retencoes(id);
+            let membro = self.env().caller();
+            if let Some(mut exemplar) = self.exemplar_retido_para(id, membro) {
+                self.retencoes.remove(exemplar.id);
+                self.liberar_exemplar(&mut exemplar);
+                self.exemplares.insert(exemplar.id, &exemplar);
+            } else {
+                let mut fila = self.reservas.get(id).unwrap_or_default();
+                let posicao = fila
+                    .iter()
+                    .position(|conta| *conta == membro)
+                    .ok_or(Error::ReservaNaoEncontrada)?;
+                fila.remove(posicao);
+                self.reservas.insert(id, &fila);
+            }
+            self.env().emit_event(ReservaCancelada {
+                livro_id: id,
+                membro,
+            });
+            Ok(())
+        }
+
+        /// Repassa ao próximo da fila os exemplares cuja janela de retirada expirou.
+        ///
+        /// As mensagens de empréstimo e reserva já fazem isso; esta mensagem
+        /// permite atualizar a fila antes de consultá-la.
+        #[ink(message)]
+        pub fn atualizar_reservas(&mut self, id: u32) -> Result<()> {
+            self.garantir_em_operacao()?;
+            if !self.livros.contains(id) {
+                return Err(Error::LivroNaoEncontrado);
+            }
+            self.processar_retencoes(id);
+            Ok(())
+        }
+
+        /// Retorna a posição de `conta` na fila de reservas do título.
+        ///
+        /// `Some(0)` indica que há um exemplar retido aguardando a retirada.
+        /// Retenções expiradas só são repassadas na próxima mensagem que altera
+        /// a fila, ver `atualizar_reservas`.
+        #[ink(message)]
+        pub fn posicao_na_fila(&self, id: u32, conta: AccountId) -> Option<u32> {
+            if self.exemplar_retido_para(id, conta).is_some() {
+                return Some(0);
+            }
+            self.reservas
+                .get(id)
+                .unwrap_or_default()
+                .iter()
+                .position(|membro| *membro == conta)
+                .map(|posicao| posicao as u32 + 1)
+        }
+
+        /// Retorna a fila de reservas de um título.
+        #[ink(message)]
+        pub fn fila_de_reservas(&self, id: u32) -> Vec<AccountId> {
+            self.reservas.get(id).unwrap_or_default()
+        }
+
+        /// Retorna a retenção de um exemplar, se houver.
+        #[ink(message)]
+        pub fn retencao(&self, exemplar_id: u32) -> Option<Retencao> {
+            self.retencoes.get(exemplar_id)
+        }
+
+        /// Garante que `conta` é um membro registrado, ativo, com assinatura
+        /// válida e com o papel `Membro`.
+        fn garantir_membro_ativo(&self, conta: AccountId) -> Result<Membro> {
+            let membro = self.membro(conta).ok_or(Error::MembroNaoRegistrado)?;
+            if !self.has_role(Papel::Membro, conta) {
+                return Err(Error::NaoAutorizado);
+            }
+            match membro.status {
+                StatusMembro::Ativo => Ok(membro),
+                StatusMembro::Expirado => Err(Error::AssinaturaExpirada),
+                StatusMembro::Suspenso => Err(Error::MembroInativo),
+            }
+        }
+
+        fn comprar_assinatura_em(&mut self, nivel: NivelMembro, moeda: Moeda) -> Result<Timestamp> {
+            let conta = self.env().caller();
+            let mut membro = self.membros.get(conta).ok_or(Error::MembroNaoRegistrado)?;
+            let inicio = membro.expira_em.max(self.env().block_timestamp());
+            membro.nivel = nivel;
+            self.pagar_assinatura(conta, &mut membro, inicio, moeda)
+        }
+
+        fn renovar_assinatura_em(&mut self, moeda: Moeda) -> Result<Timestamp> {
+            let conta = self.env().caller();
+            let mut membro = self.membros.get(conta).ok_or(Error::MembroNaoRegistrado)?;
+            let inicio = membro.expira_em.max(self.env().block_timestamp());
+            self.pagar_assinatura(conta, &mut membro, inicio, moeda)
+        }
+
+        /// Cobra um período de assinatura do nível de `membro`, contado a partir
+        /// de `inicio`, e grava o cadastro.
+        fn pagar_assinatura(
+            &mut self,
+            conta: AccountId,
+            membro: &mut Membro,
+            inicio: Timestamp,
+            moeda: Moeda,
+        ) -> Result<Timestamp> {
+            let valor = self.configuracao.preco(membro.nivel);
+            self.receber(moeda, conta, valor)?;
+            membro.expira_em = inicio.saturating_add(self.configuracao.duracao_assinatura);
+            self.membros.insert(conta, membro);
+            self.env().emit_event(AssinaturaPaga {
+                conta,
+                nivel: membro.nivel,
+                valor,
+                expira_em: membro.expira_em,
+            });
+            Ok(membro.expira_em)
+        }
+
+        /// Recebe `valor` de `pagador` em `moeda` e o soma aos fundos. No saldo
+        /// nativo, o valor transferido com a mensagem deve ser exatamente `valor`.
+        fn receber(&mut self, moeda: Moeda, pagador: AccountId, valor: Balance) -> Result<()> {
+            match moeda {
+                Moeda::Nativa => {
+                    if self.env().transferred_value() != valor {
+                        return Err(Error::ValorInvalido);
+                    }
+                }
+                Moeda::Token(token) => self.receber_em_token(token, pagador, valor)?,
+            }
+            self.creditar(moeda, valor);
+            Ok(())
+        }
+
+        /// Soma `valor` aos fundos arrecadados em `moeda`.
+        fn creditar(&mut self, moeda: Moeda, valor: Balance) {
+            match moeda {
+                Moeda::Nativa => self.fundos_disponiveis = self.fundos_disponiveis.saturating_add(valor),
+                Moeda::Token(token) => {
+                    let fundos = self.fundos_em_token(token).saturating_add(valor);
+                    self.fundos_em_token.insert(token, &fundos);
+                }
+            }
+        }
+
+        /// Envia `valor` em `moeda` do saldo do contrato para `destino`.
+        fn enviar(&mut self, moeda: Moeda, destino: AccountId, valor: Balance) -> Result<()> {
+            if valor == 0 {
+                return Ok(());
+            }
+            match moeda {
+                Moeda::Nativa => self
+                    .env()
+                    .transfer(destino, valor)
+                    .map_err(|_| Error::FalhaNaTransferencia),
+                Moeda::Token(token) => {
+                    let resultado = build_call::<Environment>()
+                        .call(token)
+                        .exec_input(
+                            ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer")))
+                                .push_arg(destino)
+                                .push_arg(valor)
+                                .push_arg(Vec::<u8>::new()),
+                        )
+                        .returns::<core::result::Result<(), PSP22Error>>()
+                        .try_invoke();
+                    erro_do_token(resultado)
+                }
+            }
+        }
+
+        /// Puxa `valor` de `pagador` para o contrato em `token`, com
+        /// `transfer_from`. O pagador precisa ter aprovado o contrato antes.
+        fn receber_em_token(&mut self, token: AccountId, pagador: AccountId, valor: Balance) -> Result<()> {
+            if valor == 0 {
+                return Ok(());
+            }
+            let resultado = build_call::<Environment>()
+                .call(token)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer_from")))
+                        .push_arg(pagador)
+                        .push_arg(self.env().account_id())
+                        .push_arg(valor)
+                        .push_arg(Vec::<u8>::new()),
+                )
+                .returns::<core::result::Result<(), PSP22Error>>()
+                .try_invoke();
+            erro_do_token(resultado)
+        }
+
+        fn token_de_pagamento(&self) -> Result<AccountId> {
+            self.configuracao.token_de_pagamento.ok_or(Error::TokenNaoConfigurado)
+        }
+
+        fn alterar_status_do_membro(&mut self, conta: AccountId, status: StatusMembro) -> Result<()> {
+            self.garantir_papel(Papel::Bibliotecario)?;
+            let mut membro = self.membros.get(conta).ok_or(Error::MembroNaoRegistrado)?;
+            membro.status = status;
+            self.membros.insert(conta, &membro);
+            self.env().emit_event(StatusDoMembroAlterado {
+                conta,
+                por: self.env().caller(),
+                status,
+            });
+            Ok(())
+        }
+
+        /// Emite até `valor` pontos de leitura para `conta`, respeitando o saldo
+        /// máximo por membro e o limite de pontos em circulação.
+        fn emitir_pontos(&mut self, conta: AccountId, valor: Balance) {
+            let saldo = PSP22::balance_of(self, conta);
+            let valor = valor
+                .min(self.configuracao.maximo_pontos_por_membro.saturating_sub(saldo))
+                .min(
+                    self.configuracao
+                        .maximo_pontos_em_circulacao
+                        .saturating_sub(self.pontos_em_circulacao),
+                );
+            if valor == 0 {
+                return;
+            }
+            self.pontos.insert(conta, &(saldo + valor));
+            self.pontos_em_circulacao += valor;
+            self.env().emit_event(PontosTransferidos {
+                de: None,
+                para: Some(conta),
+                valor,
+            });
+        }
+
+        /// Destrói `valor` pontos de leitura de `conta`.
+        fn queimar_pontos(&mut self, conta: AccountId, valor: Balance) -> Result<()> {
+            if valor == 0 {
+                return Ok(());
+            }
+            let saldo = PSP22::balance_of(self, conta);
+            if saldo < valor {
+                return Err(Error::PontosInsuficientes);
+            }
+            self.definir_pontos(conta, saldo - valor);
+            self.pontos_em_circulacao = self.pontos_em_circulacao.saturating_sub(valor);
+            self.env().emit_event(PontosTransferidos {
+                de: Some(conta),
+                para: None,
+                valor,
+            });
+            Ok(())
+        }
+
+        /// Grava o saldo de pontos de leitura de `conta`.
+        fn definir_pontos(&mut self, conta: AccountId, valor: Balance) {
+            if valor == 0 {
+                self.pontos.remove(conta);
+            } else {
+                self.pontos.insert(conta, &valor);
+            }
+        }
+
+        /// Transfere pontos de leitura entre contas.
+        fn mover_pontos(&mut self, de: AccountId, para: AccountId, valor: Balance) -> core::result::Result<(), PSP22Error> {
+            let saldo = PSP22::balance_of(self, de);
+            if saldo < valor {
+                return Err(PSP22Error::InsufficientBalance);
+            }
+            if de == para || valor == 0 {
+                return Ok(());
+            }
+            let saldo_destino = PSP22::balance_of(self, para);
+            if saldo_destino.saturating_add(valor) > self.configuracao.maximo_pontos_por_membro {
+                return Err(PSP22Error::Custom(String::from("LimiteDePontos")));
+            }
+            self.definir_pontos(de, saldo - valor);
+            self.definir_pontos(para, saldo_destino.saturating_add(valor));
+            self.env().emit_event(PontosTransferidos {
+                de: Some(de),
+                para: Some(para),
+                valor,
+            });
+            Ok(())
+        }
+
+        /// Grava a permissão de `gastador` sobre os pontos de `dono`.
+        fn definir_permissao(&mut self, dono: AccountId, gastador: AccountId, valor: Balance) {
+            if valor == 0 {
+                self.permissoes_de_pontos.remove((dono, gastador));
+            } else {
+                self.permissoes_de_pontos.insert((dono, gastador), &valor);
+            }
+            self.env().emit_event(PontosAprovados { dono, gastador, valor });
+        }
+
+        /// Move o token PSP34 de um exemplar para `para`, emitindo-o se ainda
+        /// não existir ou destruindo-o quando `para` é `None`.
+        fn transferir_custodia(&mut self, exemplar_id: u32, para: Option<AccountId>) {
+            let de = self.detentores.get(exemplar_id);
+            if de.is_none() && para.is_none() {
+                // O token de um exemplar perdido já foi queimado.
+                return;
+            }
+            if let Some(de) = de {
+                let tokens = self.tokens_por_conta.get(de).unwrap_or(0).saturating_sub(1);
+                if tokens == 0 {
+                    self.tokens_por_conta.remove(de);
+                } else {
+                    self.tokens_por_conta.insert(de, &tokens);
+                }
+            } else {
+                self.total_de_tokens = self.total_de_tokens.saturating_add(1);
+            }
+            match para {
+                Some(para) => {
+                    let tokens = self.tokens_por_conta.get(para).unwrap_or(0);
+                    self.tokens_por_conta.insert(para, &tokens.saturating_add(1));
+                    self.detentores.insert(exemplar_id, &para);
+                }
+                None => {
+                    self.detentores.remove(exemplar_id);
+                    self.total_de_tokens = self.total_de_tokens.saturating_sub(1);
+                }
+            }
+            self.env().emit_event(CustodiaTransferida {
+                de,
+                para,
+                id: Id::U32(exemplar_id),
+            });
+        }
+
+        /// Remove o registro do empréstimo e o desconta da contagem do tomador.
+        fn encerrar_emprestimo(&mut self, exemplar_id: u32, tomador: AccountId) {
+            self.emprestimos.remove(exemplar_id);
+            let ativos = self.emprestimos_ativos(tomador).saturating_sub(1);
+            if ativos == 0 {
+                self.emprestimos_ativos.remove(tomador);
+            } else {
+                self.emprestimos_ativos.insert(tomador, &ativos);
+            }
+        }
+
+        /// Grava o total de multas pendentes de `membro`.
+        fn definir_multa(&mut self, membro: AccountId, valor: Balance) {
+            if valor == 0 {
+                self.multas.remove(membro);
+            } else {
+                self.multas.insert(membro, &valor);
+            }
+        }
+
+        /// Calcula a multa de um empréstimo no instante atual.
+        fn calcular_multa(&self, emprestimo: &Emprestimo) -> Balance {
+            let atraso = self
+                .env()
+                .block_timestamp()
+                .saturating_sub(emprestimo.vencimento);
+            if atraso <= self.configuracao.carencia {
+                return 0;
+            }
+            let dias = atraso.div_ceil(DIA);
+            Balance::from(dias).saturating_mul(self.configuracao.multa_por_dia)
+        }
+
+        /// Retorna o exemplar do título retido para `membro`, se houver.
+        fn exemplar_retido_para(&self, livro_id: u32, membro: AccountId) -> Option<Exemplar> {
+            self.exemplares(livro_id).into_iter().find(|exemplar| {
+                self.retencoes
+                    .get(exemplar.id)
+                    .is_some_and(|retencao| retencao.membro == membro)
+            })
+        }
+
+        /// Destina um exemplar que voltou à estante: retido para o primeiro da
+        /// fila de reservas ou disponível, se a fila estiver vazia.
+        ///
+        /// Não persiste o exemplar; cabe ao chamador gravá-lo.
+        fn liberar_exemplar(&mut self, exemplar: &mut Exemplar) {
+            let expira_em = self
+                .env()
+                .block_timestamp()
+                .saturating_add(self.configuracao.janela_retirada);
+            self.reter_para_proximo(exemplar, expira_em);
+        }
+
+        /// Retém o exemplar para o próximo da fila até `expira_em`, ou o torna
+        /// disponível se a fila estiver vazia.
+        fn reter_para_proximo(&mut self, exemplar: &mut Exemplar, expira_em: Timestamp) {
+            let mut fila = self.reservas.get(exemplar.livro_id).unwrap_or_default();
+            if fila.is_empty() {
+                self.retencoes.remove(exemplar.id);
+                exemplar.status = StatusExemplar::Disponivel;
+                return;
+            }
+            let membro = fila.remove(0);
+            self.reservas.insert(exemplar.livro_id, &fila);
+            self.retencoes.insert(exemplar.id, &Retencao { membro, expira_em });
+            exemplar.status = StatusExemplar::Reservado;
+            self.env().emit_event(ExemplarRetido {
+                livro_id: exemplar.livro_id,
+                exemplar_id: exemplar.id,
+                membro,
+                expira_em,
+            });
+        }
+
+        /// Repassa ao próximo da fila os exemplares do título cuja janela de
+        /// retirada expirou. Cada membro seguinte recebe uma janela completa,
+        /// contada a partir do fim da anterior.
+        fn processar_retencoes(&mut self, livro_id: u32) {
+            let agora = self.env().block_timestamp();
+            let janela = self.configuracao.janela_retirada;
+            for mut exemplar in self.exemplares(livro_id) {
+                let mut expira_em = match self.retencoes.get(exemplar.id) {
+                    Some(retencao) if retencao.expira_em <= agora => retencao.expira_em,
+                    _ => continue,
+                };
+                // Pula os membros cuja janela também já terminou.
+                let mut fila = self.reservas.get(livro_id).unwrap_or_default();
+                loop {
+                    expira_em = expira_em.saturating_add(janela);
+                    if expira_em > agora || fila.is_empty() {
+                        break;
+                    }
+                    fila.remove(0);
+                }
+                self.reservas.insert(livro_id, &fila);
+                self.reter_para_proximo(&mut exemplar, expira_em);
+                self.exemplares.insert(exemplar.id, &exemplar);
+            }
+        }
+
+        /// Reserva o próximo ID de exemplar.
+        fn reservar_id_exemplar(&mut self) -> Result<u32> {
+            let exemplar_id = self.proximo_exemplar_id;
+            self.proximo_exemplar_id = exemplar_id.checked_add(1).ok_or(Error::IdEsgotado)?;
+            Ok(exemplar_id)
+        }
+
+        /// Registra um exemplar disponível para o título `livro_id`.
+        fn incluir_exemplar(&mut self, livro_id: u32, exemplar_id: u32, conservacao: Conservacao) -> Result<()> {
+            let mut exemplares = self.exemplares_por_livro.get(livro_id).unwrap_or_default();
+            if exemplares.len() >= MAXIMO_EXEMPLARES_POR_LIVRO {
+                return Err(Error::LimiteDeExemplares);
+            }
+            exemplares.push(exemplar_id);
+            self.exemplares_por_livro.insert(livro_id, &exemplares);
+            let mut exemplar = Exemplar {
+                id: exemplar_id,
+                livro_id,
+                conservacao,
+                status: StatusExemplar::Disponivel,
+            };
+            self.env().emit_event(ExemplarAdicionado {
+                livro_id,
+                exemplar_id,
+                conservacao,
+            });
+            self.transferir_custodia(exemplar_id, Some(self.env().account_id()));
+            self.processar_retencoes(livro_id);
+            self.liberar_exemplar(&mut exemplar);
+            self.exemplares.insert(exemplar_id, &exemplar);
+            Ok(())
+        }
+
+        /// Inclui um livro no índice de um gênero.
+        fn indexar_genero(&mut self, genero: GeneroId, id: u32) {
+            incluir_no_indice(
+                &mut self.livros_por_genero,
+                &mut self.posicoes_por_genero,
+                &mut self.total_por_genero,
+                genero,
+                id,
+            );
+        }
+
+        /// Retira um livro do índice de um gênero.
+        fn desindexar_genero(&mut self, genero: GeneroId, id: u32) {
+            retirar_do_indice(
+                &mut self.livros_por_genero,
+                &mut self.posicoes_por_genero,
+                &mut self.total_por_genero,
+                genero,
+                id,
+            );
+        }
+
+        /// Inclui um livro no índice de um autor.
+        fn indexar_autor(&mut self, autor: AutorId, id: u32) {
+            incluir_no_indice(
+                &mut self.livros_por_autor,
+                &mut self.posicoes_por_autor,
+                &mut self.total_por_autor,
+                autor,
+                id,
+            );
+        }
+
+        /// Retira um livro do índice de um autor.
+        fn desindexar_autor(&mut self, autor: AutorId, id: u32) {
+            retirar_do_indice(
+                &mut self.livros_por_autor,
+                &mut self.posicoes_por_autor,
+                &mut self.total_por_autor,
+                autor,
+                id,
+            );
+        }
+
+        /// Confere se os autores de um livro estão cadastrados e não se repetem.
+        fn validar_autores(&self, autores: &[AutorId]) -> Result<()> {
+            for (i, id) in autores.iter().enumerate() {
+                if autores[..i].contains(id) {
+                    return Err(Error::AutorRepetido);
+                }
+                if !self.autores.contains(id) {
+                    return Err(Error::AutorNaoEncontrado);
+                }
+            }
+            Ok(())
+        }
+
+        /// Inclui um livro nos índices de palavras-chave e de prefixos de
+        /// `titulo`, exceto nas chaves que já vêm de `titulo_anterior`.
+        fn indexar_titulo(&mut self, titulo: &str, titulo_anterior: &str, id: u32) {
+            let palavras = palavras_chave(titulo);
+            let palavras_anteriores = palavras_chave(titulo_anterior);
+            let chaves_anteriores = chaves_de_palavras(&palavras_anteriores);
+            for palavra in chaves_de_palavras(&palavras)
+                .iter()
+                .filter(|chave| !chaves_anteriores.contains(chave))
+            {
+                incluir_no_indice(
+                    &mut self.livros_por_palavra,
+                    &mut self.posicoes_por_palavra,
+                    &mut self.total_por_palavra,
+                    palavra.clone(),
+                    id,
+                );
+            }
+            let prefixos_anteriores = prefixos(&palavras_anteriores);
+            for prefixo in prefixos(&palavras) {
+                if !prefixos_anteriores.contains(&prefixo) {
+                    incluir_no_indice(
+                        &mut self.livros_por_prefixo,
+                        &mut self.posicoes_por_prefixo,
+                        &mut self.total_por_prefixo,
+                        prefixo,
+                        id,
+                    );
+                }
+            }
+        }
+
+        /// Retira um livro dos índices de palavras-chave e de prefixos de
+        /// `titulo`, exceto nas chaves que continuam em `titulo_novo`.
+        fn desindexar_titulo(&mut self, titulo: &str, titulo_novo: &str, id: u32) {
+            let palavras = palavras_chave(titulo);
+            let palavras_novas = palavras_chave(titulo_novo);
+            let chaves_novas = chaves_de_palavras(&palavras_novas);
+            for palavra in chaves_de_palavras(&palavras)
+                .iter()
+                .filter(|chave| !chaves_novas.contains(chave))
+            {
+                retirar_do_indice(
+                    &mut self.livros_por_palavra,
+                    &mut self.posicoes_por_palavra,
+                    &mut self.total_por_palavra,
+                    palavra.clone(),
+                    id,
+                );
+            }
+            let prefixos_novos = prefixos(&palavras_novas);
+            for prefixo in prefixos(&palavras) {
+                if !prefixos_novos.contains(&prefixo) {
+                    retirar_do_indice(
+                        &mut self.livros_por_prefixo,
+                        &mut self.posicoes_por_prefixo,
+                        &mut self.total_por_prefixo,
+                        prefixo,
+                        id,
+                    );
+                }
+            }
+        }
+
+        /// Valida a lista de gêneros de um livro. Gêneros descontinuados só são
+        /// aceitos se já estiverem em `atuais`.
+        fn validar_generos(&self, generos: &[GeneroId], atuais: &[GeneroId]) -> Result<()> {
+            if generos.is_empty() {
+                return Err(Error::SemGenero);
+            }
+            if generos.len() > MAXIMO_GENEROS_POR_LIVRO {
+                return Err(Error::ListaMuitoLonga);
+            }
+            for (i, id) in generos.iter().enumerate() {
+                if generos[..i].contains(id) {
+                    return Err(Error::GeneroRepetido);
+                }
+                let genero = self.generos.get(id).ok_or(Error::GeneroNaoEncontrado)?;
+                if genero.descontinuado && !atuais.contains(id) {
+                    return Err(Error::GeneroDescontinuado);
+                }
+            }
+            Ok(())
+        }
+
+        /// Garante que o contrato não está pausado nem com migração pendente.
+        fn garantir_em_operacao(&self) -> Result<()> {
+            if self.pausado() {
+                return Err(Error::ContratoPausado);
+            }
+            if self.versao_storage < VERSAO_STORAGE {
+                return Err(Error::MigracaoPendente);
+            }
+            Ok(())
+        }
+
+        /// Garante que o chamador é o dono do contrato.
+        fn garantir_dono(&self) -> Result<()> {
+            if self.env().caller() != self.dono {
+                return Err(Error::NaoAutorizado);
+            }
+            Ok(())
+        }
+
+        /// Garante que o chamador possui `papel` ou é `Admin`.
+        fn garantir_papel(&self, papel: Papel) -> Result<()> {
+            let chamador = self.env().caller();
+            if self.has_role(papel, chamador) || self.has_role(Papel::Admin, chamador) {
+                return Ok(());
+            }
+            Err(Error::NaoAutorizado)
+        }
+
+        /// Regrava o livro `id`, gravado no layout de `versao_storage`, no layout
+        /// atual.
+        ///
+        /// Cada mudança no layout de `Livro` incrementa `VERSAO_STORAGE` e
+        /// acrescenta aqui a leitura do layout anterior, com
+        /// `ink::env::get_contract_storage` sobre `(self.livros.key(), id)`. A
+        /// versão 1 é a primeira, sem layouts anteriores a converter.
+        fn migrar_livro(&mut self, _id: u32) -> Result<()> {
+            Err(Error::FalhaNaMigracao)
+        }
+
+        /// Lê um livro no layout atual. Durante uma migração, os livros ainda
+        /// não convertidos são tratados como inexistentes.
+        fn ler_livro(&self, id: u32) -> Option<Livro> {
+            if self.versao_storage < VERSAO_STORAGE && id >= self.migracao_ate.get().unwrap_or(1) {
+                return None;
+            }
+            self.livros.get(id)
+        }
+
+        /// Percorre as posições de um índice com `total` entradas a partir de
+        /// `cursor`, até `limite` resultados (no máximo `MAXIMO_POR_PAGINA`) ou
+        /// `MAXIMO_IDS_POR_VARREDURA` posições. Retorna os IDs aceitos por
+        /// `obter` e o cursor da próxima página, se houver.
+        fn varrer_indice(
+            &self,
+            total: u32,
+            cursor: u32,
+            limite: u32,
+            obter: impl Fn(u32) -> Option<u32>,
+        ) -> (Vec<u32>, Option<u32>) {
+            let limite = limite.min(MAXIMO_POR_PAGINA) as usize;
+            let fim = cursor.saturating_add(MAXIMO_IDS_POR_VARREDURA).min(total);
+            let mut ids = Vec::new();
+            let mut posicao = cursor;
+            while posicao < fim && ids.len() < limite {
+                if let Some(id) = obter(posicao) {
+                    ids.push(id);
+                }
+                posicao += 1;
+            }
+            (ids, (posicao < total).then_some(posicao))
+        }
+
+        /// Garante que o chamador pode conceder ou revogar `papel`.
+        fn garantir_gestor_de(&self, papel: Papel) -> Result<()> {
+            match papel {
+                Papel::Admin => self.garantir_dono(),
+                _ => self.garantir_papel(Papel::Admin),
+            }
+        }
+
+        fn conceder(&mut self, papel: Papel, conta: AccountId) {
+            if self.has_role(papel, conta) {
+                return;
+            }
+            self.papeis.insert((papel, conta), &());
+            self.env().emit_event(PapelConcedido {
+                papel,
+                conta,
+                por: self.env().caller(),
+            });
+        }
+
+        fn revogar(&mut self, papel: Papel, conta: AccountId) {
+            if !self.has_role(papel, conta) {
+                return;
+            }
+            self.papeis.remove((papel, conta));
+            self.env().emit_event(PapelRevogado {
+                papel,
+                conta,
+                por: self.env().caller(),
+            });
+        }
+    }
+
+    /// Cada exemplar é um token `Id::U32(exemplar_id)` da biblioteca. O token
+    /// fica com o contrato e passa ao tomador durante o empréstimo; as
+    /// transferências são feitas apenas pelo empréstimo e pela devolução, e o
+    /// token é queimado quando o exemplar é retirado ou perdido.
+    impl PSP34 for BibliotecaStorage {
+        #[ink(message)]
+        fn collection_id(&self) -> Id {
+            Id::Bytes(<AccountId as AsRef<[u8]>>::as_ref(&self.env().account_id()).to_vec())
+        }
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> u32 {
+            self.tokens_por_conta.get(owner).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        fn owner_of(&self, id: Id) -> Option<AccountId> {
+            match id {
+                Id::U32(exemplar_id) => self.detentores.get(exemplar_id),
+                _ => None,
+            }
+        }
+
+        #[ink(message)]
+        fn allowance(&self, _owner: AccountId, _operator: AccountId, _id: Option<Id>) -> bool {
+            false
+        }
+
+        #[ink(message)]
+        fn approve(&mut self, _operator: AccountId, id: Option<Id>, _approved: bool) -> core::result::Result<(), PSP34Error> {
+            self.garantir_em_operacao().map_err(|_| PSP34Error::Custom(String::from("ContratoPausado")))?;
+            if id.is_some_and(|id| self.owner_of(id).is_none()) {
+                return Err(PSP34Error::TokenNotExists);
+            }
+            Err(PSP34Error::Custom(String::from("ExemplarBloqueado")))
+        }
+
+        #[ink(message)]
+        fn transfer(&mut self, _to: AccountId, id: Id, _data: Vec<u8>) -> core::result::Result<(), PSP34Error> {
+            self.garantir_em_operacao().map_err(|_| PSP34Error::Custom(String::from("ContratoPausado")))?;
+            let dono = self.owner_of(id).ok_or(PSP34Error::TokenNotExists)?;
+            if dono != self.env().caller() {
+                return Err(PSP34Error::NotApproved);
+            }
+            Err(PSP34Error::Custom(String::from("ExemplarBloqueado")))
+        }
+
+        #[ink(message)]
+        fn total_supply(&self) -> u128 {
+            self.total_de_tokens
+        }
+    }
+
+    /// Pontos de leitura, emitidos nas devoluções feitas até o vencimento e
+    /// gastos em multas e taxas de reserva.
+    impl PSP22 for BibliotecaStorage {
+        #[ink(message)]
+        fn total_supply(&self) -> Balance {
+            self.pontos_em_circulacao
+        }
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> Balance {
+            self.pontos.get(owner).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.permissoes_de_pontos.get((owner, spender)).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        fn transfer(&mut self, to: AccountId, value: Balance, _data: Vec<u8>) -> core::result::Result<(), PSP22Error> {
+            self.garantir_em_operacao().map_err(|_| PSP22Error::Custom(String::from("ContratoPausado")))?;
+            self.mover_pontos(self.env().caller(), to, value)
+        }
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> core::result::Result<(), PSP22Error> {
+            self.garantir_em_operacao().map_err(|_| PSP22Error::Custom(String::from("ContratoPausado")))?;
+            let gastador = self.env().caller();
+            if gastador == from {
+                return self.mover_pontos(from, to, value);
+            }
+            let permissao = PSP22::allowance(self, from, gastador);
+            if permissao < value {
+                return Err(PSP22Error::InsufficientAllowance);
+            }
+            self.mover_pontos(from, to, value)?;
+            if value > 0 {
+                self.definir_permissao(from, gastador, permissao - value);
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn approve(&mut self, spender: AccountId, value: Balance) -> core::result::Result<(), PSP22Error> {
+            self.garantir_em_operacao().map_err(|_| PSP22Error::Custom(String::from("ContratoPausado")))?;
+            let dono = self.env().caller();
+            if dono != spender {
+                self.definir_permissao(dono, spender, value);
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn increase_allowance(&mut self, spender: AccountId, delta_value: Balance) -> core::result::Result<(), PSP22Error> {
+            self.garantir_em_operacao().map_err(|_| PSP22Error::Custom(String::from("ContratoPausado")))?;
+            let dono = self.env().caller();
+            if dono != spender && delta_value > 0 {
+                let permissao = PSP22::allowance(self, dono, spender);
+                self.definir_permissao(dono, spender, permissao.saturating_add(delta_value));
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn decrease_allowance(&mut self, spender: AccountId, delta_value: Balance) -> core::result::Result<(), PSP22Error> {
+            self.garantir_em_operacao().map_err(|_| PSP22Error::Custom(String::from("ContratoPausado")))?;
+            let dono = self.env().caller();
+            let permissao = PSP22::allowance(self, dono, spender);
+            if permissao < delta_value {
+                return Err(PSP22Error::InsufficientAllowance);
+            }
+            if dono != spender && delta_value > 0 {
+                self.definir_permissao(dono, spender, permissao - delta_value);
+            }
+            Ok(())
+        }
+    }
+
+    /// Converte o resultado de uma chamada ao token de pagamento.
+    fn erro_do_token(
+        resultado: core::result::Result<ink::MessageResult<core::result::Result<(), PSP22Error>>, ink::env::Error>,
+    ) -> Result<()> {
+        match resultado {
+            Ok(Ok(Ok(()))) => Ok(()),
+            Ok(Ok(Err(PSP22Error::InsufficientBalance))) => Err(Error::SaldoDeTokenInsuficiente),
+            Ok(Ok(Err(PSP22Error::InsufficientAllowance))) => Err(Error::PermissaoDeTokenInsuficiente),
+            _ => Err(Error::FalhaNoToken),
+        }
+    }
+
+    /// Valida o nome de um membro, autor ou gênero.
+    fn validar_nome(nome: &str) -> Result<()> {
+        if nome.trim().is_empty() {
+            return Err(Error::NomeVazio);
+        }
+        if nome.len() > TAMANHO_MAXIMO_NOME {
+            return Err(Error::NomeMuitoLongo);
+        }
+        Ok(())
+    }
+
+    impl Livro {
+        /// Retorna o gênero principal do livro.
+        fn genero_principal(&self) -> GeneroId {
+            self.generos.first().copied().unwrap_or(GENERO_OUTRO)
+        }
+    }
+
+    impl AtualizacaoLivro {
+        /// Aplica as alterações a `livro`, sem validá-las.
+        fn aplicar(self, livro: &mut Livro) {
+            let metadados = &mut livro.metadados;
+            if let Some(titulo) = self.titulo {
+                livro.titulo = titulo;
+            }
+            if let Some(generos) = self.generos {
+                livro.generos = generos;
+            }
+            if let Some(autores) = self.autores {
+                metadados.autores = autores;
+            }
+            if let Some(isbn10) = self.isbn10 {
+                metadados.isbn10 = isbn10;
+            }
+            if let Some(isbn13) = self.isbn13 {
+                metadados.isbn13 = isbn13;
+            }
+            if let Some(editora) = self.editora {
+                metadados.editora = editora;
+            }
+            if let Some(ano_publicacao) = self.ano_publicacao {
+                metadados.ano_publicacao = ano_publicacao;
+            }
+            if let Some(idioma) = self.idioma {
+                metadados.idioma = idioma;
+            }
+            if let Some(edicao) = self.edicao {
+                metadados.edicao = edicao;
+            }
+            if let Some(paginas) = self.paginas {
+                metadados.paginas = paginas;
+            }
+            if let Some(resumo) = self.resumo {
+                metadados.resumo = resumo;
+            }
+            if let Some(tags) = self.tags {
+                metadados.tags = tags;
+            }
+        }
+    }
+
+    /// Valida os limites de tamanho e o formato dos metadados de um livro.
+    fn validar_metadados(metadados: &Metadados) -> Result<()> {
+        if metadados.autores.len() > MAXIMO_ITENS_POR_LISTA {
+            return Err(Error::ListaMuitoLonga);
+        }
+        validar_lista(&metadados.tags, TAMANHO_MAXIMO_TAG)?;
+        for campo in [&metadados.isbn10, &metadados.isbn13, &metadados.editora] {
+            validar_tamanho(campo.as_deref(), TAMANHO_MAXIMO_CAMPO)?;
+        }
+        validar_tamanho(metadados.resumo.as_deref(), TAMANHO_MAXIMO_RESUMO)?;
+        if let Some(idioma) = &metadados.idioma {
+            let valido = (2..=3).contains(&idioma.len()) && idioma.bytes().all(|c| c.is_ascii_lowercase());
+            if !valido {
+                return Err(Error::IdiomaInvalido);
+            }
+        }
+        Ok(())
+    }
+
+    /// Valida os ISBNs dos metadados e os reescreve sem hífens, preenchendo
+    /// o ISBN-13 a partir do ISBN-10.
+    ///
+    /// Retorna a chave do índice de ISBNs, se houver ISBN.
+    fn normalizar_isbn(metadados: &mut Metadados) -> Result<Option<Isbn>> {
+        let isbn10 = analisar_isbn_com_tamanho(&mut metadados.isbn10, 10)?;
+        let isbn13 = analisar_isbn_com_tamanho(&mut metadados.isbn13, 13)?;
+        let isbn = match (isbn10, isbn13) {
+            (Some(a), Some(b)) if a != b => return Err(Error::IsbnDivergente),
+            (a, b) => b.or(a),
+        };
+        metadados.isbn13 = isbn.map(|isbn| String::from(isbn.como_str()));
+        Ok(isbn)
+    }
+
+    /// Valida um campo de ISBN que deve ter `tamanho` dígitos e o reescreve
+    /// sem hífens ou espaços.
+    fn analisar_isbn_com_tamanho(campo: &mut Option<String>, tamanho: usize) -> Result<Option<Isbn>> {
+        let Some(texto) = campo else {
+            return Ok(None);
+        };
+        let normalizado: String = texto
+            .chars()
+            .filter(|c| *c != '-' && *c != ' ')
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        if normalizado.len() != tamanho {
+            return Err(Error::IsbnInvalido);
+        }
+        let isbn = Isbn::analisar(&normalizado)?;
+        *campo = Some(normalizado);
+        Ok(Some(isbn))
+    }
+
+    /// Retorna a chave do índice de ISBNs de metadados já normalizados.
+    fn chave_isbn(metadados: &Metadados) -> Option<Isbn> {
+        metadados
+            .isbn13
+            .as_deref()
+            .and_then(|isbn| Isbn::analisar(isbn).ok())
+    }
+
+    /// Valida os dados de um autor.
+    fn validar_autor(autor: &Autor) -> Result<()> {
+        validar_nome(&autor.nome)?;
+        if autor.nacionalidade.as_deref().is_some_and(|nacionalidade| nacionalidade.trim().is_empty()) {
+            return Err(Error::CampoVazio);
+        }
+        validar_tamanho(autor.nacionalidade.as_deref(), TAMANHO_MAXIMO_CAMPO)?;
+        if let (Some(nascimento), Some(falecimento)) = (autor.ano_nascimento, autor.ano_falecimento) {
+            if falecimento < nascimento {
+                return Err(Error::AnosInvalidos);
+            }
+        }
+        Ok(())
+    }
+
+    /// Valida uma lista de tags.
+    fn validar_lista(itens: &[String], tamanho_maximo: usize) -> Result<()> {
+        if itens.len() > MAXIMO_ITENS_POR_LISTA {
+            return Err(Error::ListaMuitoLonga);
+        }
+        for item in itens {
+            if item.trim().is_empty() {
+                return Err(Error::CampoVazio);
+            }
+            validar_tamanho(Some(item), tamanho_maximo)?;
+        }
+        Ok(())
+    }
+
+    fn validar_tamanho(campo: Option<&str>, tamanho_maximo: usize) -> Result<()> {
+        match campo {
+            Some(valor) if valor.len() > tamanho_maximo => Err(Error::CampoMuitoLongo),
+            _ => Ok(()),
+        }
+    }
+
+    /// Inclui `id` ao fim da lista de `chave` em um índice secundário formado
+    /// por `ids` (chave, posição) → ID, `posicoes` (chave, ID) → posição e
+    /// `totais` chave → quantidade.
+    fn incluir_no_indice<K, I, P, T>(
+        ids: &mut Mapping<(K, u32), u32, I>,
+        posicoes: &mut Mapping<(K, u32), u32, P>,
+        totais: &mut Mapping<K, u32, T>,
+        chave: K,
+        id: u32,
+    ) where
+        K: scale::Encode + scale::EncodeLike + Clone,
+        I: StorageKey,
+        P: StorageKey,
+        T: StorageKey,
+    {
+        if posicoes.contains((chave.clone(), id)) {
+            return;
+        }
+        let total = totais.get(&chave).unwrap_or(0);
+        ids.insert((chave.clone(), total), &id);
+        posicoes.insert((chave.clone(), id), &total);
+        totais.insert(&chave, &total.saturating_add(1));
+    }
+
+    /// Retira `id` da lista de `chave` em um índice secundário, movendo o
+    /// último ID da lista para a posição liberada.
+    fn retirar_do_indice<K, I, P, T>(
+        ids: &mut Mapping<(K, u32), u32, I>,
+        posicoes: &mut Mapping<(K, u32), u32, P>,
+        totais: &mut Mapping<K, u32, T>,
+        chave: K,
+        id: u32,
+    ) where
+        K: scale::Encode + scale::EncodeLike + Clone,
+        I: StorageKey,
+        P: StorageKey,
+        T: StorageKey,
+    {
+        let Some(posicao) = posicoes.take((chave.clone(), id)) else {
+            return;
+        };
+        let ultima = totais.get(&chave).unwrap_or(1).saturating_sub(1);
+        if posicao != ultima {
+            if let Some(ultimo_id) = ids.get((chave.clone(), ultima)) {
+                ids.insert((chave.clone(), posicao), &ultimo_id);
+                posicoes.insert((chave.clone(), ultimo_id), &posicao);
+            }
+        }
+        ids.remove((chave.clone(), ultima));
+        if ultima == 0 {
+            totais.remove(&chave);
+        } else {
+            totais.insert(&chave, &ultima);
+        }
+    }
+
+    /// Converte um texto para minúsculas e remove os acentos.
+    fn normalizar_texto(texto: &str) -> String {
+        texto.chars().flat_map(char::to_lowercase).map(sem_acento).collect()
+    }
+
+    fn sem_acento(c: char) -> char {
+        match c {
+            'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            'ñ' => 'n',
+            _ => c,
+        }
+    }
+
+    /// Extrai as palavras-chave de um título normalizado, sem palavras vazias
+    /// e sem repetições.
+    fn palavras_chave(titulo: &str) -> Vec<String> {
+        let normalizado = normalizar_texto(titulo);
+        let mut palavras: Vec<String> = normalizado
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|palavra| !palavra.is_empty() && !PALAVRAS_VAZIAS.contains(palavra))
+            .map(String::from)
+            .collect();
+        palavras.sort_unstable();
+        palavras.dedup();
+        palavras
+    }
+
+    /// Retorna as chaves do índice de palavras: as palavras-chave cortadas em
+    /// `TAMANHO_MAXIMO_PALAVRA` caracteres, sem repetições.
+    fn chaves_de_palavras(palavras: &[String]) -> Vec<String> {
+        let mut chaves: Vec<String> = palavras
+            .iter()
+            .map(|palavra| palavra.chars().take(TAMANHO_MAXIMO_PALAVRA).collect())
+            .collect();
+        chaves.sort_unstable();
+        chaves.dedup();
+        chaves
+    }
+
+    /// Retorna os prefixos indexados de um conjunto de palavras-chave, entre
+    /// `TAMANHO_MINIMO_PREFIXO` e `TAMANHO_MAXIMO_PREFIXO` caracteres.
+    fn prefixos(palavras: &[String]) -> Vec<String> {
+        let mut prefixos = Vec::new();
+        for palavra in palavras {
+            let mut prefixo = String::new();
+            for (tamanho, c) in palavra.chars().take(TAMANHO_MAXIMO_PREFIXO).enumerate() {
+                prefixo.push(c);
+                if tamanho + 1 >= TAMANHO_MINIMO_PREFIXO {
+                    prefixos.push(prefixo.clone());
+                }
+            }
+        }
+        prefixos.sort_unstable();
+        prefixos.dedup();
+        prefixos
+    }
+
+    /// Valida o título de um livro.
+    fn validar_titulo(titulo: &str) -> Result<()> {
+        if titulo.trim().is_empty() {
+            return Err(Error::TituloVazio);
+        }
+        if titulo.len() > TAMANHO_MAXIMO_TITULO {
+            return Err(Error::TituloMuitoLongo);
+        }
+        Ok(())
+    }
+
+    /// Testes para verificar o funcionamento do contrato
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn contas() -> ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment> {
+            ink::env::test::default_accounts::<ink::env::DefaultEnvironment>()
+        }
+
+        fn renomear(titulo: String, genero: GeneroId) -> AtualizacaoLivro {
+            AtualizacaoLivro {
+                titulo: Some(titulo),
+                generos: Some(vec![genero]),
+                ..AtualizacaoLivro::default()
+            }
+        }
+
+        fn definir_chamador(conta: AccountId) {
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(conta);
+        }
+
+        /// Registra `conta` como membro com assinatura `Basico` e devolve a
+        /// chamada para alice.
+        fn registrar(contract: &mut BibliotecaStorage, conta: AccountId) {
+            definir_chamador(conta);
+            contract.registrar_membro("Membro".into()).unwrap();
+            contract.comprar_assinatura(NivelMembro::Basico).unwrap();
+            definir_chamador(contas().alice);
+        }
+
+        fn definir_valor_transferido(valor: Balance) {
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(valor);
+        }
+
+        fn definir_instante(instante: Timestamp) {
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(instante);
+        }
+
+        type Evento = <BibliotecaStorage as ink::reflect::ContractEventBase>::Type;
+
+        fn eventos() -> Vec<Evento> {
+            ink::env::test::recorded_events()
+                .map(|evento| {
+                    <Evento as scale::Decode>::decode(&mut &evento.data[..])
+                        .expect("evento inválido")
+                })
+                .collect()
+        }
+
+        #[ink::test]
+        fn test_adicionar_livro() {
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            assert_eq!(id, 1);
+        }
+
+        #[ink::test]
+        fn test_listar_livros() {
+            let mut contract = BibliotecaStorage::new();
+            contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            let livros = contract.listar_livros();
+            assert_eq!(livros.len(), 1);
+            assert_eq!(livros[0].titulo, "Livro A");
+        }
+
+        #[ink::test]
+        fn test_atualizar_livro() {
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Antigo".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            let atualizado = contract.atualizar_livro(id, renomear("Novo".into(), GENERO_ROMANCE));
+            assert_eq!(atualizado, Ok(()));
+            let livros = contract.listar_livros();
+            assert_eq!(livros[0].titulo, "Novo");
+        }
+
+        #[ink::test]
+        fn test_remover_livro() {
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro Removível".into(), vec![GENERO_OUTRO], Metadados::default()).unwrap();
+            assert_eq!(contract.listar_livros().len(), 1);
+            let removido = contract.remover_livro(id);
+            assert_eq!(removido, Ok(()));
+            assert_eq!(contract.listar_livros().len(), 0);
+        }
+
+        #[ink::test]
+        fn test_remover_livro_mantem_indice() {
+            let mut contract = BibliotecaStorage::new();
+            let a = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            let b = contract.adicionar_livro("Livro B".into(), vec![GENERO_POESIA], Metadados::default()).unwrap();
+            let c = contract.adicionar_livro("Livro C".into(), vec![GENERO_ROMANCE], Metadados::default()).unwrap();
+            assert_eq!(contract.remover_livro(a), Ok(()));
+            assert_eq!(contract.remover_livro(a), Err(Error::LivroNaoEncontrado));
+            assert_eq!(contract.total_livros(), 2);
+            let mut ids: Vec<u32> = contract.listar_livros().iter().map(|livro| livro.id).collect();
+            ids.sort();
+            assert_eq!(ids, vec![b, c]);
+            assert_eq!(contract.versao_storage(), VERSAO_STORAGE);
+        }
+
+        #[ink::test]
+        fn test_titulo_invalido() {
+            let mut contract = BibliotecaStorage::new();
+            assert_eq!(contract.adicionar_livro("   ".into(), vec![GENERO_FICCAO], Metadados::default()), Err(Error::TituloVazio));
+            let longo = "a".repeat(TAMANHO_MAXIMO_TITULO + 1);
+            assert_eq!(contract.adicionar_livro(longo, vec![GENERO_FICCAO], Metadados::default()), Err(Error::TituloMuitoLongo));
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            assert_eq!(contract.atualizar_livro(id, renomear("".into(), GENERO_FICCAO)), Err(Error::TituloVazio));
+        }
+
+        #[ink::test]
+        fn test_atualizar_livro_inexistente() {
+            let mut contract = BibliotecaStorage::new();
+            assert_eq!(
+                contract.atualizar_livro(42, renomear("Novo".into(), GENERO_ROMANCE)),
+                Err(Error::LivroNaoEncontrado)
+            );
+        }
+
+        #[ink::test]
+        fn test_id_esgotado() {
+            let mut contract = BibliotecaStorage::new();
+            contract.proximo_id = u32::MAX;
+            assert_eq!(contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()), Err(Error::IdEsgotado));
+            assert_eq!(contract.total_livros(), 0);
+        }
+
+        #[ink::test]
+        fn test_somente_bibliotecario_altera_catalogo() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            definir_chamador(contas.bob);
+            assert_eq!(contract.adicionar_livro("Livro B".into(), vec![GENERO_FICCAO], Metadados::default()), Err(Error::NaoAutorizado));
+            assert_eq!(contract.atualizar_livro(id, renomear("Novo".into(), GENERO_FICCAO)), Err(Error::NaoAutorizado));
+            assert_eq!(contract.remover_livro(id), Err(Error::NaoAutorizado));
+
+            definir_chamador(contas.alice);
+            assert_eq!(contract.grant_role(Papel::Bibliotecario, contas.bob), Ok(()));
+            definir_chamador(contas.bob);
+            assert!(contract.adicionar_livro("Livro B".into(), vec![GENERO_FICCAO], Metadados::default()).is_ok());
+            assert_eq!(contract.renounce_role(Papel::Bibliotecario), Ok(()));
+            assert!(!contract.has_role(Papel::Bibliotecario, contas.bob));
+            assert_eq!(contract.remover_livro(id), Err(Error::NaoAutorizado));
+        }
+
+        #[ink::test]
+        fn test_conceder_e_revogar_papeis() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            assert!(contract.has_role(Papel::Admin, contas.alice));
+            contract.grant_role(Papel::Admin, contas.bob).unwrap();
+
+            // Um admin que não é dono não pode gerenciar o papel Admin.
+            definir_chamador(contas.bob);
+            assert_eq!(contract.grant_role(Papel::Admin, contas.charlie), Err(Error::NaoAutorizado));
+            assert_eq!(contract.grant_role(Papel::Membro, contas.charlie), Ok(()));
+            assert!(contract.has_role(Papel::Membro, contas.charlie));
+
+            definir_chamador(contas.alice);
+            assert_eq!(contract.revoke_role(Papel::Membro, contas.charlie), Ok(()));
+            assert!(!contract.has_role(Papel::Membro, contas.charlie));
+
+            definir_chamador(contas.charlie);
+            assert_eq!(contract.grant_role(Papel::Membro, contas.django), Err(Error::NaoAutorizado));
+            // Admin (bob), Membro (charlie) e a revogação de Membro.
+            assert_eq!(ink::env::test::recorded_events().count(), 3);
+        }
+
+        #[ink::test]
+        fn test_transferencia_de_propriedade() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            definir_chamador(contas.bob);
+            assert_eq!(contract.transfer_ownership(contas.bob), Err(Error::NaoAutorizado));
+
+            definir_chamador(contas.alice);
+            contract.transfer_ownership(contas.bob).unwrap();
+            assert_eq!(contract.pending_owner(), Some(contas.bob));
+            assert_eq!(contract.owner(), contas.alice);
+
+            definir_chamador(contas.charlie);
+            assert_eq!(contract.accept_ownership(), Err(Error::NaoAutorizado));
+            definir_chamador(contas.bob);
+            assert_eq!(contract.accept_ownership(), Ok(()));
+            assert_eq!(contract.owner(), contas.bob);
+            assert_eq!(contract.pending_owner(), None);
+            assert!(contract.has_role(Papel::Admin, contas.bob));
+            assert!(!contract.has_role(Papel::Admin, contas.alice));
+
+            definir_chamador(contas.alice);
+            assert_eq!(contract.grant_role(Papel::Bibliotecario, contas.charlie), Err(Error::NaoAutorizado));
+        }
+
+        #[ink::test]
+        fn test_eventos_do_catalogo() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Antigo".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            contract.atualizar_livro(id, renomear("Novo".into(), GENERO_POESIA)).unwrap();
+            contract.remover_livro(id).unwrap();
+
+            let eventos = eventos();
+            assert_eq!(eventos.len(), 6);
+            match &eventos[0] {
+                Evento::LivroAdicionado(evento) => {
+                    assert_eq!(evento.id, id);
+                    assert_eq!(evento.genero, GENERO_FICCAO);
+                    assert_eq!(evento.por, contas.alice);
+                    assert_eq!(evento.titulo, "Antigo");
+                }
+                _ => panic!("esperava LivroAdicionado"),
+            }
+            assert!(matches!(eventos[1], Evento::ExemplarAdicionado(_)));
+            assert!(matches!(eventos[2], Evento::CustodiaTransferida(_)));
+            match &eventos[3] {
+                Evento::LivroAtualizado(evento) => {
+                    assert_eq!(evento.titulo_anterior, "Antigo");
+                    assert_eq!(evento.titulo_novo, "Novo");
+                    assert_eq!(evento.generos_anteriores, vec![GENERO_FICCAO]);
+                    assert_eq!(evento.genero, GENERO_POESIA);
+                }
+                _ => panic!("esperava LivroAtualizado"),
+            }
+            assert!(matches!(eventos[4], Evento::CustodiaTransferida(_)));
+            match &eventos[5] {
+                Evento::LivroRemovido(evento) => {
+                    assert_eq!(evento.id, id);
+                    assert_eq!(evento.titulo, "Novo");
+                }
+                _ => panic!("esperava LivroRemovido"),
+            }
+        }
+
+        #[ink::test]
+        fn test_topicos_dos_eventos() {
+            let mut contract = BibliotecaStorage::new();
+            contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            let evento = ink::env::test::recorded_events().next().unwrap();
+            // Assinatura do evento mais os tópicos de ID, gênero e chamador.
+            assert_eq!(evento.topics.len(), 4);
+        }
+
+        #[ink::test]
+        fn test_falha_nao_emite_evento() {
+            let mut contract = BibliotecaStorage::new();
+            assert!(contract.remover_livro(7).is_err());
+            assert_eq!(ink::env::test::recorded_events().count(), 0);
+        }
+
+        #[ink::test]
+        fn test_emprestar_e_devolver_livro() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            registrar(&mut contract, contas.bob);
+
+            definir_instante(1_000);
+            definir_chamador(contas.bob);
+            let exemplar_id = contract.emprestar_livro(id).unwrap();
+            assert_eq!(contract.exemplar(exemplar_id).unwrap().status, StatusExemplar::Emprestado);
+            let emprestimo = contract.emprestimo(exemplar_id).unwrap();
+            assert_eq!(emprestimo.tomador, contas.bob);
+            assert_eq!(emprestimo.inicio, 1_000);
+            assert_eq!(emprestimo.vencimento, 1_000 + PRAZO_EMPRESTIMO);
+            assert_eq!(contract.emprestar_livro(id), Err(Error::LivroIndisponivel));
+
+            assert_eq!(contract.devolver_livro(exemplar_id), Ok(()));
+            assert_eq!(contract.exemplar(exemplar_id).unwrap().status, StatusExemplar::Disponivel);
+            assert_eq!(contract.emprestimo(exemplar_id), None);
+            assert_eq!(contract.devolver_livro(exemplar_id), Err(Error::LivroNaoEmprestado));
+        }
+
+        #[ink::test]
+        fn test_emprestimo_exige_membro() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            definir_chamador(contas.bob);
+            assert_eq!(contract.emprestar_livro(id), Err(Error::MembroNaoRegistrado));
+            assert_eq!(contract.emprestar_livro(99), Err(Error::MembroNaoRegistrado));
+
+            // Sem o papel `Membro`, o cadastro sozinho não permite empréstimos.
+            registrar(&mut contract, contas.bob);
+            contract.revoke_role(Papel::Membro, contas.bob).unwrap();
+            definir_chamador(contas.bob);
+            assert_eq!(contract.emprestar_livro(id), Err(Error::NaoAutorizado));
+            definir_chamador(contas.alice);
+            contract.grant_role(Papel::Membro, contas.bob).unwrap();
+            definir_chamador(contas.bob);
+            assert!(contract.emprestar_livro(id).is_ok());
+        }
+
+        #[ink::test]
+        fn test_devolucao_por_terceiro() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            registrar(&mut contract, contas.bob);
+            registrar(&mut contract, contas.charlie);
+            definir_chamador(contas.bob);
+            let exemplar_id = contract.emprestar_livro(id).unwrap();
+
+            definir_chamador(contas.charlie);
+            assert_eq!(contract.devolver_livro(exemplar_id), Err(Error::NaoAutorizado));
+            // Um bibliotecário pode registrar a devolução em nome do tomador.
+            definir_chamador(contas.alice);
+            assert_eq!(contract.devolver_livro(exemplar_id), Ok(()));
+        }
+
+        #[ink::test]
+        fn test_remover_livro_emprestado() {
+            let mut contract = BibliotecaStorage::new();
+            registrar(&mut contract, contas().alice);
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            let exemplar_id = contract.emprestar_livro(id).unwrap();
+            assert_eq!(contract.remover_livro(id), Err(Error::EmprestimoAtivo));
+            contract.devolver_livro(exemplar_id).unwrap();
+            assert_eq!(contract.remover_livro(id), Ok(()));
+            assert_eq!(contract.exemplar(exemplar_id), None);
+        }
+
+        #[ink::test]
+        fn test_emprestimo_escolhe_exemplar_livre() {
+            let mut contract = BibliotecaStorage::new();
+            registrar(&mut contract, contas().alice);
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            let segundo = contract.adicionar_exemplar(id, Conservacao::Bom).unwrap();
+            assert_eq!(contract.exemplares(id).len(), 2);
+            assert_eq!(contract.exemplares_disponiveis(id).len(), 2);
+
+            let primeiro = contract.emprestar_livro(id).unwrap();
+            assert_ne!(primeiro, segundo);
+            let disponiveis = contract.exemplares_disponiveis(id);
+            assert_eq!(disponiveis.len(), 1);
+            assert_eq!(disponiveis[0].id, segundo);
+            assert_eq!(contract.emprestar_livro(id), Ok(segundo));
+            assert_eq!(contract.emprestar_livro(id), Err(Error::LivroIndisponivel));
+        }
+
+        #[ink::test]
+        fn test_adicionar_e_retirar_exemplares() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            assert_eq!(contract.adicionar_exemplar(99, Conservacao::Bom), Err(Error::LivroNaoEncontrado));
+            let exemplar_id = contract.adicionar_exemplar(id, Conservacao::Regular).unwrap();
+            assert_eq!(contract.definir_conservacao(exemplar_id, Conservacao::Danificado), Ok(()));
+            assert_eq!(contract.exemplar(exemplar_id).unwrap().conservacao, Conservacao::Danificado);
+
+            definir_chamador(contas.bob);
+            assert_eq!(contract.retirar_exemplar(exemplar_id), Err(Error::NaoAutorizado));
+            definir_chamador(contas.alice);
+            assert_eq!(contract.retirar_exemplar(exemplar_id), Ok(()));
+            assert_eq!(contract.retirar_exemplar(exemplar_id), Err(Error::ExemplarNaoEncontrado));
+            assert_eq!(contract.exemplares(id).len(), 1);
+
+            registrar(&mut contract, contas.alice);
+            let emprestado = contract.emprestar_livro(id).unwrap();
+            assert_eq!(contract.retirar_exemplar(emprestado), Err(Error::EmprestimoAtivo));
+        }
+
+        #[ink::test]
+        fn test_limite_de_exemplares() {
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            for _ in 1..MAXIMO_EXEMPLARES_POR_LIVRO {
+                contract.adicionar_exemplar(id, Conservacao::Bom).unwrap();
+            }
+            assert_eq!(contract.adicionar_exemplar(id, Conservacao::Bom), Err(Error::LimiteDeExemplares));
+        }
+
+        /// Cria um contrato com um título de um único exemplar, emprestado a bob,
+        /// e com charlie e django na fila de reservas.
+        fn contrato_com_fila() -> (BibliotecaStorage, u32, u32) {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            for conta in [contas.bob, contas.charlie, contas.django] {
+                registrar(&mut contract, conta);
+            }
+            definir_chamador(contas.bob);
+            let exemplar_id = contract.emprestar_livro(id).unwrap();
+            definir_chamador(contas.charlie);
+            assert_eq!(contract.reservar_livro(id), Ok(1));
+            definir_chamador(contas.django);
+            assert_eq!(contract.reservar_livro(id), Ok(2));
+            (contract, id, exemplar_id)
+        }
+
+        #[ink::test]
+        fn test_reservar_livro() {
+            let contas = contas();
+            let (mut contract, id, exemplar_id) = contrato_com_fila();
+            assert_eq!(contract.reservar_livro(id), Err(Error::ReservaExistente));
+            assert_eq!(contract.fila_de_reservas(id), vec![contas.charlie, contas.django]);
+
+            definir_instante(1_000);
+            definir_chamador(contas.bob);
+            contract.devolver_livro(exemplar_id).unwrap();
+            assert_eq!(contract.exemplar(exemplar_id).unwrap().status, StatusExemplar::Reservado);
+            let janela = contract.configuracao().janela_retirada;
+            assert_eq!(
+                contract.retencao(exemplar_id),
+                Some(Retencao {
+                    membro: contas.charlie,
+                    expira_em: 1_000 + janela,
+                })
+            );
+            assert_eq!(contract.posicao_na_fila(id, contas.charlie), Some(0));
+            assert_eq!(contract.posicao_na_fila(id, contas.django), Some(1));
+            assert_eq!(contract.posicao_na_fila(id, contas.bob), None);
+
+            // O exemplar retido não pode ser emprestado a outro membro.
+            assert_eq!(contract.emprestar_livro(id), Err(Error::LivroIndisponivel));
+            definir_chamador(contas.charlie);
+            assert_eq!(contract.emprestar_livro(id), Ok(exemplar_id));
+            assert_eq!(contract.retencao(exemplar_id), None);
+        }
+
+        #[ink::test]
+        fn test_reserva_com_exemplar_disponivel() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            registrar(&mut contract, contas.alice);
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            assert_eq!(contract.reservar_livro(id), Err(Error::ExemplarDisponivel));
+            assert_eq!(contract.reservar_livro(99), Err(Error::LivroNaoEncontrado));
+            definir_chamador(contas.bob);
+            assert_eq!(contract.reservar_livro(id), Err(Error::MembroNaoRegistrado));
+        }
+
+        #[ink::test]
+        fn test_retencao_expira_e_passa_ao_proximo() {
+            let contas = contas();
+            let (mut contract, id, exemplar_id) = contrato_com_fila();
+            let janela = contract.configuracao().janela_retirada;
+            definir_chamador(contas.bob);
+            contract.devolver_livro(exemplar_id).unwrap();
+
+            definir_instante(janela);
+            contract.atualizar_reservas(id).unwrap();
+            let retencao = contract.retencao(exemplar_id).unwrap();
+            assert_eq!(retencao.membro, contas.django);
+            assert_eq!(retencao.expira_em, 2 * janela);
+            assert_eq!(contract.posicao_na_fila(id, contas.charlie), None);
+
+            // Com a fila vazia, o exemplar volta a ficar disponível.
+            definir_instante(10 * janela);
+            definir_chamador(contas.charlie);
+            assert_eq!(contract.emprestar_livro(id), Ok(exemplar_id));
+        }
+
+        #[ink::test]
+        fn test_cancelar_reserva() {
+            let contas = contas();
+            let (mut contract, id, exemplar_id) = contrato_com_fila();
+            definir_chamador(contas.bob);
+            assert_eq!(contract.cancelar_reserva(id), Err(Error::ReservaNaoEncontrada));
+            contract.devolver_livro(exemplar_id).unwrap();
+
+            // charlie abre mão do exemplar retido, que passa para django.
+            definir_chamador(contas.charlie);
+            assert_eq!(contract.cancelar_reserva(id), Ok(()));
+            assert_eq!(contract.retencao(exemplar_id).unwrap().membro, contas.django);
+
+            definir_chamador(contas.django);
+            assert_eq!(contract.cancelar_reserva(id), Ok(()));
+            assert_eq!(contract.retencao(exemplar_id), None);
+            assert_eq!(contract.exemplar(exemplar_id).unwrap().status, StatusExemplar::Disponivel);
+        }
+
+        #[ink::test]
+        fn test_novo_exemplar_atende_fila() {
+            let contas = contas();
+            let (mut contract, id, _) = contrato_com_fila();
+            definir_chamador(contas.alice);
+            let novo = contract.adicionar_exemplar(id, Conservacao::Novo).unwrap();
+            assert_eq!(contract.retencao(novo).unwrap().membro, contas.charlie);
+            assert_eq!(contract.retirar_exemplar(novo), Err(Error::ExemplarReservado));
+        }
+
+        #[ink::test]
+        fn test_definir_configuracao() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let configuracao = Configuracao {
+                janela_retirada: DIA,
+                ..Configuracao::default()
+            };
+            definir_chamador(contas.bob);
+            assert_eq!(contract.definir_configuracao(configuracao.clone()), Err(Error::NaoAutorizado));
+            definir_chamador(contas.alice);
+            assert_eq!(contract.definir_configuracao(configuracao.clone()), Ok(()));
+            assert_eq!(contract.configuracao(), configuracao);
+        }
+
+        #[ink::test]
+        fn test_renovar_emprestimo() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            registrar(&mut contract, contas.bob);
+            let Configuracao {
+                prazo_renovacao,
+                maximo_renovacoes,
+                ..
+            } = contract.configuracao();
+
+            definir_chamador(contas.bob);
+            let exemplar_id = contract.emprestar_livro(id).unwrap();
+            let vencimento = contract.emprestimo(exemplar_id).unwrap().vencimento;
+            for renovacao in 1..=maximo_renovacoes {
+                assert_eq!(
+                    contract.renovar_emprestimo(exemplar_id),
+                    Ok(vencimento + prazo_renovacao * renovacao as u64)
+                );
+            }
+            assert_eq!(contract.renovar_emprestimo(exemplar_id), Err(Error::LimiteDeRenovacoes));
+            assert_eq!(contract.emprestimo(exemplar_id).unwrap().renovacoes, maximo_renovacoes);
+        }
+
+        #[ink::test]
+        fn test_renovacao_recusada_com_reserva() {
+            let contas = contas();
+            let (mut contract, _, exemplar_id) = contrato_com_fila();
+            definir_chamador(contas.bob);
+            assert_eq!(contract.renovar_emprestimo(exemplar_id), Err(Error::ReservaPendente));
+            definir_chamador(contas.charlie);
+            assert_eq!(contract.renovar_emprestimo(exemplar_id), Err(Error::NaoAutorizado));
+            assert_eq!(contract.renovar_emprestimo(99), Err(Error::LivroNaoEmprestado));
+        }
+
+        /// Cria um contrato em que bob devolve um exemplar `dias_de_atraso` após o vencimento.
+        fn contrato_com_atraso(dias_de_atraso: u64) -> BibliotecaStorage {
+            let contas = contas();
+            definir_chamador(contas.alice);
+            definir_instante(0);
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            registrar(&mut contract, contas.bob);
+            definir_chamador(contas.bob);
+            let exemplar_id = contract.emprestar_livro(id).unwrap();
+            definir_instante(PRAZO_EMPRESTIMO + dias_de_atraso * DIA);
+            contract.devolver_livro(exemplar_id).unwrap();
+            contract
+        }
+
+        #[ink::test]
+        fn test_multa_por_atraso() {
+            let contas = contas();
+            let multa_por_dia = Configuracao::default().multa_por_dia;
+            assert_eq!(contrato_com_atraso(3).multa_pendente(contas.bob), 3 * multa_por_dia);
+        }
+
+        #[ink::test]
+        fn test_multa_do_emprestimo_em_andamento() {
+            let mut contract = BibliotecaStorage::new();
+            registrar(&mut contract, contas().alice);
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            let exemplar_id = contract.emprestar_livro(id).unwrap();
+            // Dentro da carência não há multa.
+            definir_instante(PRAZO_EMPRESTIMO + DIA);
+            assert_eq!(contract.multa_do_emprestimo(exemplar_id), 0);
+            definir_instante(PRAZO_EMPRESTIMO + 2 * DIA + 1);
+            assert_eq!(contract.multa_do_emprestimo(exemplar_id), 3 * contract.configuracao().multa_por_dia);
+        }
+
+        #[ink::test]
+        fn test_multas_impedem_emprestimo() {
+            let contas = contas();
+            let mut contract = contrato_com_atraso(20);
+            let devido = contract.multa_pendente(contas.bob);
+            assert!(devido > contract.configuracao().limite_multa);
+            assert_eq!(contract.emprestar_livro(1), Err(Error::MultasPendentes));
+
+            definir_valor_transferido(devido + 1);
+            assert_eq!(contract.pagar_multa(), Err(Error::ValorInvalido));
+            definir_valor_transferido(devido - 1);
+            assert_eq!(contract.pagar_multa(), Ok(1));
+            definir_valor_transferido(0);
+            assert!(contract.emprestar_livro(1).is_ok());
+            assert_eq!(contract.fundos_disponiveis(), devido - 1);
+        }
+
+        #[ink::test]
+        fn test_sacar_fundos() {
+            let contas = contas();
+            let mut contract = contrato_com_atraso(3);
+            let devido = contract.multa_pendente(contas.bob);
+            definir_valor_transferido(devido);
+            assert_eq!(contract.pagar_multa(), Ok(0));
+            let contrato = ink::env::test::callee::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contrato, devido);
+
+            assert_eq!(contract.sacar_fundos(devido, contas.bob), Err(Error::NaoAutorizado));
+            definir_chamador(contas.alice);
+            assert_eq!(contract.sacar_fundos(devido + 1, contas.eve), Err(Error::SaldoInsuficiente));
+            let saldo_inicial =
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(contas.eve).unwrap();
+            assert_eq!(contract.sacar_fundos(devido, contas.eve), Ok(()));
+            assert_eq!(contract.fundos_disponiveis(), 0);
+            assert_eq!(
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(contas.eve),
+                Ok(saldo_inicial + devido)
+            );
+            assert!(matches!(eventos().last(), Some(Evento::FundosSacados(_))));
+        }
+
+        fn saldo(conta: AccountId) -> Balance {
+            ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(conta).unwrap_or(0)
+        }
+
+        /// Cria um contrato com um título de caução `caucao`, emprestado a bob.
+        fn contrato_com_caucao(caucao: Balance) -> (BibliotecaStorage, u32, u32) {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro Raro".into(), vec![GENERO_BIOGRAFIA], Metadados::default()).unwrap();
+            registrar(&mut contract, contas.bob);
+            contract.definir_caucao(id, caucao).unwrap();
+            let contrato = ink::env::test::callee::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contrato, caucao);
+
+            definir_chamador(contas.bob);
+            definir_valor_transferido(caucao - 1);
+            assert_eq!(contract.emprestar_livro(id), Err(Error::ValorInvalido));
+            definir_valor_transferido(caucao);
+            let exemplar_id = contract.emprestar_livro(id).unwrap();
+            definir_valor_transferido(0);
+            (contract, id, exemplar_id)
+        }
+
+        #[ink::test]
+        fn test_caucao_reembolsada() {
+            let contas = contas();
+            let (mut contract, _, exemplar_id) = contrato_com_caucao(50_000);
+            assert_eq!(contract.emprestimo(exemplar_id).unwrap().caucao, 50_000);
+            let saldo_inicial = saldo(contas.bob);
+            assert_eq!(contract.devolver_livro(exemplar_id), Ok(()));
+            assert_eq!(saldo(contas.bob), saldo_inicial + 50_000);
+            assert_eq!(contract.fundos_disponiveis(), 0);
+        }
+
+        #[ink::test]
+        fn test_sacar_reembolso_pendente() {
+            let contas = contas();
+            let (mut contract, _, exemplar_id) = contrato_com_caucao(50_000);
+            assert_eq!(contract.devolver_livro(exemplar_id), Ok(()));
+            assert_eq!(contract.reembolso_pendente(contas.bob, Moeda::Nativa), 0);
+            assert_eq!(contract.sacar_reembolso(Moeda::Nativa), Err(Error::ValorInvalido));
+
+            // O ambiente de testes não simula falhas de transferência; grava o
+            // reembolso pendente como `devolver_livro` faria.
+            contract.reembolsos_pendentes.insert((contas.bob, Moeda::Nativa), &30_000);
+            let contrato = ink::env::test::callee::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contrato, 30_000);
+            let saldo_inicial = saldo(contas.bob);
+            definir_chamador(contas.charlie);
+            assert_eq!(contract.sacar_reembolso(Moeda::Nativa), Err(Error::ValorInvalido));
+            definir_chamador(contas.bob);
+            assert_eq!(contract.sacar_reembolso(Moeda::Nativa), Ok(30_000));
+            assert_eq!(saldo(contas.bob), saldo_inicial + 30_000);
+            assert_eq!(contract.reembolso_pendente(contas.bob, Moeda::Nativa), 0);
+        }
+
+        #[ink::test]
+        fn test_caucao_desconta_multa_e_dano() {
+            let contas = contas();
+            let (mut contract, _, exemplar_id) = contrato_com_caucao(50_000);
+            definir_chamador(contas.alice);
+            assert_eq!(contract.registrar_dano(exemplar_id, 0), Err(Error::ValorInvalido));
+            contract.registrar_dano(exemplar_id, 10_000).unwrap();
+            definir_instante(PRAZO_EMPRESTIMO + 3 * DIA);
+            let multa = contract.multa_do_emprestimo(exemplar_id);
+
+            let saldo_inicial = saldo(contas.bob);
+            definir_chamador(contas.bob);
+            contract.devolver_livro(exemplar_id).unwrap();
+            let retido = 10_000 + multa;
+            assert_eq!(saldo(contas.bob), saldo_inicial + 50_000 - retido);
+            assert_eq!(contract.fundos_disponiveis(), retido);
+            assert_eq!(contract.multa_pendente(contas.bob), 0);
+        }
+
+        #[ink::test]
+        fn test_encargos_acima_da_caucao_viram_multa() {
+            let contas = contas();
+            let (mut contract, _, exemplar_id) = contrato_com_caucao(5_000);
+            definir_chamador(contas.alice);
+            contract.registrar_dano(exemplar_id, 8_000).unwrap();
+            contract.devolver_livro(exemplar_id).unwrap();
+            assert_eq!(contract.multa_pendente(contas.bob), 3_000);
+            assert_eq!(contract.fundos_disponiveis(), 5_000);
+        }
+
+        #[ink::test]
+        fn test_caucao_perdida_com_exemplar() {
+            let contas = contas();
+            let (mut contract, _, exemplar_id) = contrato_com_caucao(50_000);
+            assert_eq!(contract.marcar_perdido(exemplar_id), Err(Error::NaoAutorizado));
+            definir_chamador(contas.alice);
+            assert_eq!(contract.marcar_perdido(exemplar_id), Ok(()));
+            assert_eq!(contract.exemplar(exemplar_id).unwrap().status, StatusExemplar::Perdido);
+            assert_eq!(contract.emprestimo(exemplar_id), None);
+            assert_eq!(contract.fundos_disponiveis(), 50_000);
+            assert_eq!(contract.devolver_livro(exemplar_id), Err(Error::LivroNaoEmprestado));
+        }
+
+        #[ink::test]
+        fn test_registrar_membro() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            definir_instante(500);
+            definir_chamador(contas.bob);
+            assert_eq!(contract.registrar_membro("  ".into()), Err(Error::NomeVazio));
+            let longo = "b".repeat(TAMANHO_MAXIMO_NOME + 1);
+            assert_eq!(contract.registrar_membro(longo), Err(Error::NomeMuitoLongo));
+            assert_eq!(contract.registrar_membro("Bob".into()), Ok(()));
+            assert_eq!(contract.registrar_membro("Bob".into()), Err(Error::MembroJaRegistrado));
+            assert_eq!(
+                contract.membro(contas.bob),
+                Some(Membro {
+                    nome: "Bob".into(),
+                    desde: 500,
+                    nivel: NivelMembro::Basico,
+                    status: StatusMembro::Expirado,
+                    expira_em: 0,
+                })
+            );
+            assert!(contract.has_role(Papel::Membro, contas.bob));
+        }
+
+        #[ink::test]
+        fn test_atualizar_membro() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            registrar(&mut contract, contas.bob);
+            definir_chamador(contas.bob);
+            assert_eq!(contract.atualizar_membro(contas.bob, Some("Roberto".into()), None), Ok(()));
+            assert_eq!(
+                contract.atualizar_membro(contas.bob, None, Some(NivelMembro::Premium)),
+                Err(Error::NaoAutorizado)
+            );
+            definir_chamador(contas.charlie);
+            assert_eq!(
+                contract.atualizar_membro(contas.bob, Some("Outro".into()), None),
+                Err(Error::NaoAutorizado)
+            );
+            definir_chamador(contas.alice);
+            assert_eq!(contract.atualizar_membro(contas.bob, None, Some(NivelMembro::Premium)), Ok(()));
+            let membro = contract.membro(contas.bob).unwrap();
+            assert_eq!(membro.nome, "Roberto");
+            assert_eq!(membro.nivel, NivelMembro::Premium);
+            assert_eq!(
+                contract.atualizar_membro(contas.charlie, Some("Charlie".into()), None),
+                Err(Error::MembroNaoRegistrado)
+            );
+        }
+
+        #[ink::test]
+        fn test_suspender_e_reativar_membro() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            registrar(&mut contract, contas.bob);
+            assert_eq!(contract.suspender_membro(contas.bob), Ok(()));
+            definir_chamador(contas.bob);
+            assert_eq!(contract.reativar_membro(contas.bob), Err(Error::NaoAutorizado));
+            assert_eq!(contract.emprestar_livro(id), Err(Error::MembroInativo));
+
+            definir_chamador(contas.alice);
+            assert_eq!(contract.reativar_membro(contas.bob), Ok(()));
+            definir_chamador(contas.bob);
+            assert!(contract.emprestar_livro(id).is_ok());
+        }
+
+        #[ink::test]
+        fn test_regras_por_nivel() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            for _ in 0..5 {
+                contract.adicionar_exemplar(id, Conservacao::Bom).unwrap();
+            }
+            registrar(&mut contract, contas.bob);
+            let regras = contract.configuracao().regras(NivelMembro::Basico);
+
+            definir_chamador(contas.bob);
+            for _ in 0..regras.maximo_emprestimos {
+                contract.emprestar_livro(id).unwrap();
+            }
+            assert_eq!(contract.emprestar_livro(id), Err(Error::LimiteDeEmprestimos));
+            assert_eq!(contract.emprestimos_ativos(contas.bob), regras.maximo_emprestimos);
+
+            definir_chamador(contas.alice);
+            contract.atualizar_membro(contas.bob, None, Some(NivelMembro::Premium)).unwrap();
+            let premium = contract.configuracao().regras(NivelMembro::Premium);
+            definir_chamador(contas.bob);
+            let exemplar_id = contract.emprestar_livro(id).unwrap();
+            assert_eq!(contract.emprestimo(exemplar_id).unwrap().vencimento, premium.prazo_emprestimo);
+            contract.devolver_livro(exemplar_id).unwrap();
+            assert_eq!(contract.emprestimos_ativos(contas.bob), regras.maximo_emprestimos);
+        }
+
+        #[ink::test]
+        fn test_comprar_e_renovar_assinatura() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let configuracao = Configuracao {
+                preco_estudante: 2_000,
+                ..Configuracao::default()
+            };
+            contract.definir_configuracao(configuracao.clone()).unwrap();
+            let duracao = configuracao.duracao_assinatura;
+
+            definir_chamador(contas.bob);
+            assert_eq!(contract.comprar_assinatura(NivelMembro::Estudante), Err(Error::MembroNaoRegistrado));
+            contract.registrar_membro("Bob".into()).unwrap();
+            definir_instante(1_000);
+            assert_eq!(contract.comprar_assinatura(NivelMembro::Estudante), Err(Error::ValorInvalido));
+            definir_valor_transferido(2_000);
+            assert_eq!(contract.comprar_assinatura(NivelMembro::Estudante), Ok(1_000 + duracao));
+            let membro = contract.membro(contas.bob).unwrap();
+            assert_eq!(membro.nivel, NivelMembro::Estudante);
+            assert_eq!(membro.status, StatusMembro::Ativo);
+
+            // Renovar antes do fim acumula o período restante.
+            definir_instante(2_000);
+            assert_eq!(contract.renovar_assinatura(), Ok(1_000 + 2 * duracao));
+            // Comprar com a assinatura válida também preserva o tempo já pago.
+            definir_instante(3_000);
+            assert_eq!(contract.comprar_assinatura(NivelMembro::Estudante), Ok(1_000 + 3 * duracao));
+            // Depois do fim, o novo período começa agora.
+            definir_instante(10 * duracao);
+            assert_eq!(contract.membro(contas.bob).unwrap().status, StatusMembro::Expirado);
+            assert_eq!(contract.renovar_assinatura(), Ok(11 * duracao));
+            assert_eq!(contract.fundos_disponiveis(), 8_000);
+        }
+
+        #[ink::test]
+        fn test_assinatura_expirada() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            contract.adicionar_exemplar(id, Conservacao::Bom).unwrap();
+            registrar(&mut contract, contas.bob);
+            let duracao = contract.configuracao().duracao_assinatura;
+
+            definir_chamador(contas.bob);
+            let exemplar_id = contract.emprestar_livro(id).unwrap();
+            definir_instante(duracao);
+            assert_eq!(contract.emprestar_livro(id), Err(Error::AssinaturaExpirada));
+            assert_eq!(contract.reservar_livro(id), Err(Error::AssinaturaExpirada));
+            assert_eq!(contract.renovar_emprestimo(exemplar_id), Err(Error::AssinaturaExpirada));
+
+            // A devolução e o pagamento de multas continuam permitidos.
+            assert_eq!(contract.devolver_livro(exemplar_id), Ok(()));
+            let devido = contract.multa_pendente(contas.bob);
+            assert!(devido > 0);
+            definir_valor_transferido(devido);
+            assert_eq!(contract.pagar_multa(), Ok(0));
+        }
+
+        /// Cria um contrato com "Machado de Assis" cadastrado como autor 1,
+        /// usado por `metadados_completos`.
+        fn contrato_com_autor() -> BibliotecaStorage {
+            let mut contract = BibliotecaStorage::new();
+            let autor = contract
+                .adicionar_autor("Machado de Assis".into(), Some(1839), Some(1908), Some("Brasil".into()))
+                .unwrap();
+            assert_eq!(autor, 1);
+            contract
+        }
+
+        fn metadados_completos() -> Metadados {
+            Metadados {
+                autores: vec![1],
+                isbn10: Some("8535911154".into()),
+                isbn13: Some("9788535911152".into()),
+                editora: Some("Companhia das Letras".into()),
+                ano_publicacao: Some(1899),
+                idioma: Some("pt".into()),
+                edicao: Some(3),
+                paginas: Some(256),
+                resumo: Some("Bentinho e Capitu.".into()),
+                tags: vec!["classico".into(), "realismo".into()],
+            }
+        }
+
+        #[ink::test]
+        fn test_adicionar_livro_com_metadados() {
+            let mut contract = contrato_com_autor();
+            let id = contract
+                .adicionar_livro("Dom Casmurro".into(), vec![GENERO_ROMANCE], metadados_completos())
+                .unwrap();
+            assert_eq!(contract.listar_livros()[0].metadados, metadados_completos());
+            assert_eq!(contract.listar_livros()[0].id, id);
+        }
+
+        #[ink::test]
+        fn test_limites_de_metadados() {
+            let mut contract = BibliotecaStorage::new();
+            let casos = [
+                (
+                    Metadados {
+                        autores: vec![99],
+                        ..Metadados::default()
+                    },
+                    Error::AutorNaoEncontrado,
+                ),
+                (
+                    Metadados {
+                        tags: vec!["t".into(); MAXIMO_ITENS_POR_LISTA + 1],
+                        ..Metadados::default()
+                    },
+                    Error::ListaMuitoLonga,
+                ),
+                (
+                    Metadados {
+                        editora: Some("e".repeat(TAMANHO_MAXIMO_CAMPO + 1)),
+                        ..Metadados::default()
+                    },
+                    Error::CampoMuitoLongo,
+                ),
+                (
+                    Metadados {
+                        resumo: Some("r".repeat(TAMANHO_MAXIMO_RESUMO + 1)),
+                        ..Metadados::default()
+                    },
+                    Error::CampoMuitoLongo,
+                ),
+                (
+                    Metadados {
+                        idioma: Some("PT".into()),
+                        ..Metadados::default()
+                    },
+                    Error::IdiomaInvalido,
+                ),
+            ];
+            for (metadados, erro) in casos {
+                assert_eq!(contract.adicionar_livro("Livro".into(), vec![GENERO_OUTRO], metadados), Err(erro));
+            }
+            assert_eq!(contract.total_livros(), 0);
+        }
+
+        #[ink::test]
+        fn test_atualizar_campos_individuais() {
+            let mut contract = contrato_com_autor();
+            let id = contract
+                .adicionar_livro("Dom Casmurro".into(), vec![GENERO_ROMANCE], metadados_completos())
+                .unwrap();
+            let alteracoes = AtualizacaoLivro {
+                paginas: Some(Some(300)),
+                resumo: Some(None),
+                tags: Some(vec!["brasil".into()]),
+                ..AtualizacaoLivro::default()
+            };
+            assert_eq!(contract.atualizar_livro(id, alteracoes), Ok(()));
+
+            let livro = contract.listar_livros().pop().unwrap();
+            assert_eq!(livro.titulo, "Dom Casmurro");
+            assert_eq!(livro.generos, vec![GENERO_ROMANCE]);
+            assert_eq!(livro.metadados.paginas, Some(300));
+            assert_eq!(livro.metadados.resumo, None);
+            assert_eq!(livro.metadados.tags, vec![String::from("brasil")]);
+            assert_eq!(livro.metadados.autores, metadados_completos().autores);
+
+            let invalida = AtualizacaoLivro {
+                idioma: Some(Some("portugues".into())),
+                ..AtualizacaoLivro::default()
+            };
+            assert_eq!(contract.atualizar_livro(id, invalida), Err(Error::IdiomaInvalido));
+            assert_eq!(contract.listar_livros()[0].metadados.paginas, Some(300));
+        }
+
+        #[ink::test]
+        fn test_validar_isbn() {
+            assert_eq!(Isbn::analisar("0-306-40615-2").unwrap().como_str(), "9780306406157");
+            assert_eq!(Isbn::analisar("978-0-306-40615-7").unwrap().como_str(), "9780306406157");
+            assert_eq!(Isbn::analisar("080442957X").unwrap().como_str(), "9780804429573");
+            assert_eq!(Isbn::analisar("0306406153"), Err(Error::IsbnInvalido));
+            assert_eq!(Isbn::analisar("9780306406158"), Err(Error::IsbnInvalido));
+            assert_eq!(Isbn::analisar("X306406152"), Err(Error::IsbnInvalido));
+            assert_eq!(Isbn::analisar("97803064061570"), Err(Error::IsbnInvalido));
+        }
+
+        #[ink::test]
+        fn test_normalizar_isbn() {
+            let mut contract = BibliotecaStorage::new();
+            let metadados = Metadados {
+                isbn10: Some("0-306-40615-2".into()),
+                ..Metadados::default()
+            };
+            contract.adicionar_livro("Livro A".into(), vec![GENERO_OUTRO], metadados).unwrap();
+            let livro = contract.buscar_por_isbn("9780306406157".into()).unwrap();
+            assert_eq!(livro.metadados.isbn10.as_deref(), Some("0306406152"));
+            assert_eq!(livro.metadados.isbn13.as_deref(), Some("9780306406157"));
+
+            let divergente = Metadados {
+                isbn10: Some("0306406152".into()),
+                isbn13: Some("9788535911152".into()),
+                ..Metadados::default()
+            };
+            assert_eq!(
+                contract.adicionar_livro("Livro B".into(), vec![GENERO_OUTRO], divergente),
+                Err(Error::IsbnDivergente)
+            );
+            let trocado = Metadados {
+                isbn13: Some("0306406152".into()),
+                ..Metadados::default()
+            };
+            assert_eq!(
+                contract.adicionar_livro("Livro B".into(), vec![GENERO_OUTRO], trocado),
+                Err(Error::IsbnInvalido)
+            );
+        }
+
+        #[ink::test]
+        fn test_isbn_duplicado() {
+            let mut contract = contrato_com_autor();
+            let id = contract
+                .adicionar_livro("Dom Casmurro".into(), vec![GENERO_ROMANCE], metadados_completos())
+                .unwrap();
+            let mesma_edicao = Metadados {
+                isbn13: Some("978-85-359-1115-2".into()),
+                ..Metadados::default()
+            };
+            assert_eq!(
+                contract.adicionar_livro("Outro".into(), vec![GENERO_ROMANCE], mesma_edicao),
+                Err(Error::IsbnDuplicado)
+            );
+            assert_eq!(contract.buscar_por_isbn("85-359-1115-4".into()).unwrap().id, id);
+            assert_eq!(contract.buscar_por_isbn("invalido".into()), None);
+        }
+
+        #[ink::test]
+        fn test_indice_de_isbn_acompanha_alteracoes() {
+            let mut contract = contrato_com_autor();
+            let id = contract
+                .adicionar_livro("Dom Casmurro".into(), vec![GENERO_ROMANCE], metadados_completos())
+                .unwrap();
+            let outro = contract
+                .adicionar_livro("Outro".into(), vec![GENERO_OUTRO], Metadados::default())
+                .unwrap();
+            let mesmo_isbn = AtualizacaoLivro {
+                isbn13: Some(Some("9788535911152".into())),
+                ..AtualizacaoLivro::default()
+            };
+            assert_eq!(contract.atualizar_livro(outro, mesmo_isbn), Err(Error::IsbnDuplicado));
+
+            let novo_isbn = AtualizacaoLivro {
+                isbn10: Some(None),
+                isbn13: Some(Some("9780306406157".into())),
+                ..AtualizacaoLivro::default()
+            };
+            assert_eq!(contract.atualizar_livro(id, novo_isbn), Ok(()));
+            assert_eq!(contract.buscar_por_isbn("9788535911152".into()), None);
+            assert_eq!(contract.buscar_por_isbn("9780306406157".into()).unwrap().id, id);
+
+            contract.remover_livro(id).unwrap();
+            assert_eq!(contract.buscar_por_isbn("9780306406157".into()), None);
+        }
+
+        #[ink::test]
+        fn test_generos_iniciais() {
+            let contract = BibliotecaStorage::new();
+            let generos = contract.listar_generos();
+            assert_eq!(generos.len(), GENEROS_INICIAIS.len());
+            let romance = contract.genero(GENERO_ROMANCE).unwrap();
+            assert_eq!(romance.nome, "Romance");
+            assert_eq!(romance.pai, None);
+        }
+
+        #[ink::test]
+        fn test_criar_e_renomear_genero() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            definir_chamador(contas.bob);
+            assert_eq!(contract.criar_genero("Ciência".into(), None), Err(Error::NaoAutorizado));
+            definir_chamador(contas.alice);
+            let ciencia = contract.criar_genero("Ciência".into(), None).unwrap();
+            assert_eq!(contract.criar_genero("Ciência".into(), None), Err(Error::GeneroDuplicado));
+            assert_eq!(contract.criar_genero("Física".into(), Some(99)), Err(Error::GeneroNaoEncontrado));
+            let fisica = contract.criar_genero("Física".into(), Some(ciencia)).unwrap();
+            assert_eq!(contract.genero(fisica).unwrap().pai, Some(ciencia));
+
+            assert_eq!(contract.renomear_genero(ciencia, "Física".into()), Err(Error::GeneroDuplicado));
+            assert_eq!(contract.renomear_genero(ciencia, "Ciências".into()), Ok(()));
+            assert_eq!(contract.genero(ciencia).unwrap().nome, "Ciências");
+            // O nome antigo fica livre para outro gênero.
+            assert!(contract.criar_genero("Ciência".into(), None).is_ok());
+        }
+
+        #[ink::test]
+        fn test_livro_com_varios_generos() {
+            let mut contract = BibliotecaStorage::new();
+            let historia = contract.criar_genero("História".into(), None).unwrap();
+            let id = contract
+                .adicionar_livro("Livro A".into(), vec![historia, GENERO_BIOGRAFIA], Metadados::default())
+                .unwrap();
+            assert_eq!(contract.listar_livros()[0].generos, vec![historia, GENERO_BIOGRAFIA]);
+            match eventos().into_iter().find(|evento| matches!(evento, Evento::LivroAdicionado(_))) {
+                Some(Evento::LivroAdicionado(evento)) => assert_eq!(evento.genero, historia),
+                _ => panic!("esperava LivroAdicionado"),
+            }
+
+            let casos = [
+                (vec![], Error::SemGenero),
+                (vec![historia, historia], Error::GeneroRepetido),
+                (vec![99], Error::GeneroNaoEncontrado),
+                (vec![GENERO_OUTRO; MAXIMO_GENEROS_POR_LIVRO + 1], Error::ListaMuitoLonga),
+            ];
+            for (generos, erro) in casos {
+                let alteracoes = AtualizacaoLivro {
+                    generos: Some(generos),
+                    ..AtualizacaoLivro::default()
+                };
+                assert_eq!(contract.atualizar_livro(id, alteracoes), Err(erro));
+            }
+        }
+
+        #[ink::test]
+        fn test_genero_descontinuado() {
+            let mut contract = BibliotecaStorage::new();
+            let id = contract
+                .adicionar_livro("Livro A".into(), vec![GENERO_INFANTIL], Metadados::default())
+                .unwrap();
+            assert_eq!(contract.descontinuar_genero(GENERO_INFANTIL), Ok(()));
+            assert!(contract.genero(GENERO_INFANTIL).unwrap().descontinuado);
+            assert_eq!(
+                contract.adicionar_livro("Livro B".into(), vec![GENERO_INFANTIL], Metadados::default()),
+                Err(Error::GeneroDescontinuado)
+            );
+            assert_eq!(contract.criar_genero("Fábulas".into(), Some(GENERO_INFANTIL)), Err(Error::GeneroDescontinuado));
+            // O livro que já tinha o gênero pode mantê-lo.
+            let alteracoes = AtualizacaoLivro {
+                generos: Some(vec![GENERO_INFANTIL, GENERO_POESIA]),
+                ..AtualizacaoLivro::default()
+            };
+            assert_eq!(contract.atualizar_livro(id, alteracoes), Ok(()));
+        }
+
+        #[ink::test]
+        fn test_listar_por_genero_paginado() {
+            let mut contract = BibliotecaStorage::new();
+            let mut ids = Vec::new();
+            for titulo in ["A", "B", "C", "D", "E"] {
+                ids.push(
+                    contract
+                        .adicionar_livro(titulo.into(), vec![GENERO_POESIA], Metadados::default())
+                        .unwrap(),
+                );
+            }
+            contract
+                .adicionar_livro("F".into(), vec![GENERO_ROMANCE], Metadados::default())
+                .unwrap();
+
+            let (pagina, cursor) = contract.listar_por_genero(GENERO_POESIA, 0, 2);
+            assert_eq!(pagina.iter().map(|livro| livro.id).collect::<Vec<_>>(), ids[..2]);
+            assert_eq!(cursor, Some(2));
+            let (pagina, cursor) = contract.listar_por_genero(GENERO_POESIA, 4, 2);
+            assert_eq!(pagina.len(), 1);
+            assert_eq!(cursor, None);
+            let (pagina, _) = contract.listar_por_genero(GENERO_POESIA, 0, u32::MAX);
+            assert_eq!(pagina.len(), 5);
+            assert_eq!(contract.listar_por_genero(99, 0, 10), (Vec::new(), None));
+        }
+
+        #[ink::test]
+        fn test_indice_de_genero_consistente() {
+            let mut contract = BibliotecaStorage::new();
+            let a = contract
+                .adicionar_livro("A".into(), vec![GENERO_POESIA, GENERO_INFANTIL], Metadados::default())
+                .unwrap();
+            let b = contract
+                .adicionar_livro("B".into(), vec![GENERO_POESIA], Metadados::default())
+                .unwrap();
+            let c = contract
+                .adicionar_livro("C".into(), vec![GENERO_POESIA], Metadados::default())
+                .unwrap();
+            let contagem = contract.contar_por_genero();
+            assert!(contagem.contains(&(GENERO_POESIA, 3)));
+            assert!(contagem.contains(&(GENERO_INFANTIL, 1)));
+            assert!(contagem.contains(&(GENERO_ROMANCE, 0)));
+
+            let alteracoes = AtualizacaoLivro {
+                generos: Some(vec![GENERO_INFANTIL, GENERO_ROMANCE]),
+                ..AtualizacaoLivro::default()
+            };
+            assert_eq!(contract.atualizar_livro(a, alteracoes), Ok(()));
+            assert_eq!(contract.remover_livro(b), Ok(()));
+
+            let contagem = contract.contar_por_genero();
+            assert!(contagem.contains(&(GENERO_POESIA, 1)));
+            assert!(contagem.contains(&(GENERO_INFANTIL, 1)));
+            assert!(contagem.contains(&(GENERO_ROMANCE, 1)));
+            let ids = |genero| {
+                contract
+                    .listar_por_genero(genero, 0, MAXIMO_POR_PAGINA)
+                    .0
+                    .iter()
+                    .map(|livro| livro.id)
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(ids(GENERO_POESIA), vec![c]);
+            assert_eq!(ids(GENERO_ROMANCE), vec![a]);
+            assert_eq!(ids(GENERO_INFANTIL), vec![a]);
+        }
+
+        #[ink::test]
+        fn test_listar_livros_paginado() {
+            let mut contract = BibliotecaStorage::new();
+            let ids: Vec<u32> = (0..5)
+                .map(|i| {
+                    contract
+                        .adicionar_livro(format!("Livro {i}"), vec![GENERO_OUTRO], Metadados::default())
+                        .unwrap()
+                })
+                .collect();
+            assert_eq!(contract.remover_livro(ids[1]), Ok(()));
+
+            let (pagina, cursor) = contract.listar_livros_paginado(None, 2);
+            assert_eq!(pagina.iter().map(|livro| livro.id).collect::<Vec<_>>(), vec![ids[0], ids[2]]);
+            assert_eq!(cursor, Some(ids[3]));
+            let (pagina, cursor) = contract.listar_livros_paginado(cursor, 2);
+            assert_eq!(pagina.iter().map(|livro| livro.id).collect::<Vec<_>>(), vec![ids[3], ids[4]]);
+            assert_eq!(cursor, None);
+            assert_eq!(contract.listar_livros_paginado(Some(100), 2), (Vec::new(), None));
+        }
+
+        #[ink::test]
+        fn test_listar_livros_paginado_limita_pagina() {
+            let mut contract = BibliotecaStorage::new();
+            for i in 0..MAXIMO_POR_PAGINA + 1 {
+                contract
+                    .adicionar_livro(format!("Livro {i}"), vec![GENERO_OUTRO], Metadados::default())
+                    .unwrap();
+            }
+            let (pagina, cursor) = contract.listar_livros_paginado(None, u32::MAX);
+            assert_eq!(pagina.len(), MAXIMO_POR_PAGINA as usize);
+            assert_eq!(cursor, Some(MAXIMO_POR_PAGINA + 1));
+            assert_eq!(contract.listar_livros().len(), MAXIMO_POR_PAGINA as usize + 1);
+        }
+
+        #[ink::test]
+        fn test_obter_livros_por_id() {
+            let mut contract = BibliotecaStorage::new();
+            let a = contract
+                .adicionar_livro("A".into(), vec![GENERO_OUTRO], Metadados::default())
+                .unwrap();
+            let b = contract
+                .adicionar_livro("B".into(), vec![GENERO_OUTRO], Metadados::default())
+                .unwrap();
+            assert_eq!(contract.remover_livro(a), Ok(()));
+
+            assert!(!contract.existe_livro(a));
+            assert!(contract.existe_livro(b));
+            assert_eq!(contract.obter_livro(a), None);
+            assert_eq!(contract.obter_livro(b).unwrap().titulo, "B");
+            let livros = contract.obter_livros(vec![b, a, 99, b]).unwrap();
+            assert_eq!(livros.len(), 4);
+            assert_eq!(livros[0].as_ref().map(|livro| livro.id), Some(b));
+            assert_eq!(livros[1], None);
+            assert_eq!(livros[2], None);
+            assert_eq!(livros[3], livros[0]);
+            let maximo = MAXIMO_POR_PAGINA as usize;
+            assert_eq!(contract.obter_livros(vec![b; maximo]).map(|livros| livros.len()), Ok(maximo));
+            assert_eq!(contract.obter_livros(vec![b; maximo + 1]), Err(Error::ListaMuitoLonga));
+        }
+
+        #[ink::test]
+        fn test_palavras_chave_normalizadas() {
+            assert_eq!(
+                palavras_chave("O Cortiço de Aluísio: a Edição"),
+                vec!["aluisio", "cortico", "edicao"]
+            );
+            assert_eq!(palavras_chave("A casa, a CASA"), vec!["casa"]);
+            assert!(palavras_chave("o de a").is_empty());
+        }
+
+        #[ink::test]
+        fn test_buscar_por_titulo() {
+            let mut contract = BibliotecaStorage::new();
+            let cortico = contract
+                .adicionar_livro("O Cortiço".into(), vec![GENERO_ROMANCE], Metadados::default())
+                .unwrap();
+            let casa = contract
+                .adicionar_livro("A Casa de Pensão".into(), vec![GENERO_ROMANCE], Metadados::default())
+                .unwrap();
+            let casamento = contract
+                .adicionar_livro("Casamenteiros e Pensão".into(), vec![GENERO_ROMANCE], Metadados::default())
+                .unwrap();
+
+            assert_eq!(contract.buscar_por_titulo("cortico".into(), 0, 10).0, vec![cortico]);
+            assert_eq!(contract.buscar_por_titulo("PENSÃO".into(), 0, 10).0, vec![casa, casamento]);
+            assert_eq!(contract.buscar_por_titulo("casa da pensao".into(), 0, 10).0, vec![casa]);
+            assert_eq!(contract.buscar_por_titulo("pensao".into(), 0, 1).0.len(), 1);
+            assert!(contract.buscar_por_titulo("de".into(), 0, 10).0.is_empty());
+
+            assert_eq!(contract.buscar_por_prefixo("Cas".into(), 0, 10).0, vec![casa, casamento]);
+            assert_eq!(contract.buscar_por_prefixo("casamen".into(), 0, 10).0, vec![casamento]);
+            assert_eq!(contract.buscar_por_prefixo("casamenteir".into(), 0, 10).0, vec![casamento]);
+            assert!(contract.buscar_por_prefixo("casamenteirx".into(), 0, 10).0.is_empty());
+            assert!(contract.buscar_por_prefixo("c".into(), 0, 10).0.is_empty());
+        }
+
+        #[ink::test]
+        fn test_palavras_longas_no_indice_de_titulos() {
+            use scale::Encode;
+            let mut contract = BibliotecaStorage::new();
+            let silicose = contract
+                .adicionar_livro(
+                    "Pneumoultramicroscopicossilicovulcanoconiótico".into(),
+                    vec![GENERO_OUTRO],
+                    Metadados::default(),
+                )
+                .unwrap();
+            let coniose = contract
+                .adicionar_livro(
+                    "Pneumoultramicroscopicossilicovulcanoconiose".into(),
+                    vec![GENERO_OUTRO],
+                    Metadados::default(),
+                )
+                .unwrap();
+            assert_eq!(
+                contract
+                    .buscar_por_titulo("pneumoultramicroscopicossilicovulcanoconiose".into(), 0, 10)
+                    .0,
+                vec![coniose]
+            );
+            assert_eq!(
+                contract.buscar_por_prefixo("pneumoultramicroscopico".into(), 0, 10).0,
+                vec![silicose, coniose]
+            );
+
+            // Uma palavra do tamanho máximo do título gera uma chave curta.
+            let titulo = "ã".repeat(TAMANHO_MAXIMO_TITULO / 2);
+            let id = contract
+                .adicionar_livro(titulo.clone(), vec![GENERO_OUTRO], Metadados::default())
+                .unwrap();
+            assert_eq!(contract.buscar_por_titulo(titulo, 0, 10).0, vec![id]);
+            let maior = "\u{10000}".repeat(TAMANHO_MAXIMO_PALAVRA);
+            let chave = (contract.livros_por_palavra.key(), (maior, u32::MAX)).encode();
+            assert!(chave.len() <= 128);
+        }
+
+        #[ink::test]
+        fn test_buscar_por_titulo_paginado() {
+            let mut contract = BibliotecaStorage::new();
+            for _ in 0..MAXIMO_IDS_POR_VARREDURA {
+                contract
+                    .adicionar_livro("Contos Gauchescos".into(), vec![GENERO_FICCAO], Metadados::default())
+                    .unwrap();
+            }
+            let tardios: Vec<u32> = (0..3)
+                .map(|_| {
+                    contract
+                        .adicionar_livro("Contos Tardios".into(), vec![GENERO_FICCAO], Metadados::default())
+                        .unwrap()
+                })
+                .collect();
+
+            let (pagina, cursor) = contract.buscar_por_titulo("contos".into(), 0, 100);
+            assert_eq!(pagina.len(), MAXIMO_POR_PAGINA as usize);
+            assert_eq!(cursor, Some(MAXIMO_POR_PAGINA));
+
+            // Os livros além das primeiras posições do índice continuam
+            // alcançáveis pelo cursor.
+            let mut encontrados = Vec::new();
+            let mut cursor = Some(0);
+            while let Some(posicao) = cursor {
+                let (pagina, proximo) = contract.buscar_por_prefixo("cont".into(), posicao, MAXIMO_POR_PAGINA);
+                encontrados.extend(pagina);
+                cursor = proximo;
+            }
+            assert_eq!(encontrados.len(), MAXIMO_IDS_POR_VARREDURA as usize + 3);
+            assert!(tardios.iter().all(|id| encontrados.contains(id)));
+        }
+
+        #[ink::test]
+        fn test_indice_de_titulos_consistente() {
+            let mut contract = BibliotecaStorage::new();
+            let id = contract
+                .adicionar_livro("Dom Casmurro".into(), vec![GENERO_ROMANCE], Metadados::default())
+                .unwrap();
+            assert_eq!(contract.atualizar_livro(id, renomear("Dom Quixote".into(), GENERO_ROMANCE)), Ok(()));
+            assert_eq!(contract.buscar_por_titulo("dom".into(), 0, 10).0, vec![id]);
+            assert!(contract.buscar_por_titulo("casmurro".into(), 0, 10).0.is_empty());
+            assert!(contract.buscar_por_prefixo("casm".into(), 0, 10).0.is_empty());
+            assert_eq!(contract.buscar_por_prefixo("quix".into(), 0, 10).0, vec![id]);
+
+            assert_eq!(contract.remover_livro(id), Ok(()));
+            assert!(contract.buscar_por_titulo("dom".into(), 0, 10).0.is_empty());
+            assert!(contract.buscar_por_prefixo("quix".into(), 0, 10).0.is_empty());
+        }
+
+        #[ink::test]
+        fn test_registro_de_autores() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            definir_chamador(contas.bob);
+            assert_eq!(
+                contract.adicionar_autor("Clarice Lispector".into(), None, None, None),
+                Err(Error::NaoAutorizado)
+            );
+            definir_chamador(contas.alice);
+            assert_eq!(
+                contract.adicionar_autor("Clarice Lispector".into(), Some(1977), Some(1920), None),
+                Err(Error::AnosInvalidos)
+            );
+            assert_eq!(contract.adicionar_autor("".into(), None, None, None), Err(Error::NomeVazio));
+            let id = contract
+                .adicionar_autor("Clarice Lispector".into(), Some(1920), None, Some("Brasil".into()))
+                .unwrap();
+            assert_eq!(
+                contract.atualizar_autor(id, "Clarice Lispector".into(), Some(1920), Some(1977), Some("Brasil".into())),
+                Ok(())
+            );
+            assert_eq!(contract.autor(id).unwrap().ano_falecimento, Some(1977));
+            assert_eq!(
+                contract.atualizar_autor(99, "Outro".into(), None, None, None),
+                Err(Error::AutorNaoEncontrado)
+            );
+        }
+
+        #[ink::test]
+        fn test_listar_por_autor() {
+            let mut contract = contrato_com_autor();
+            let clarice = contract
+                .adicionar_autor("Clarice Lispector".into(), None, None, None)
+                .unwrap();
+            let com_autores = |autores: Vec<AutorId>| Metadados {
+                autores,
+                ..Metadados::default()
+            };
+            let a = contract
+                .adicionar_livro("A".into(), vec![GENERO_OUTRO], com_autores(vec![1]))
+                .unwrap();
+            let b = contract
+                .adicionar_livro("B".into(), vec![GENERO_OUTRO], com_autores(vec![1, clarice]))
+                .unwrap();
+            assert_eq!(
+                contract.adicionar_livro("C".into(), vec![GENERO_OUTRO], com_autores(vec![clarice, clarice])),
+                Err(Error::AutorRepetido)
+            );
+
+            let ids = |contract: &BibliotecaStorage, autor| {
+                contract
+                    .listar_por_autor(autor, 0, MAXIMO_POR_PAGINA)
+                    .0
+                    .iter()
+                    .map(|livro| livro.id)
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(ids(&contract, 1), vec![a, b]);
+            assert_eq!(ids(&contract, clarice), vec![b]);
+            assert_eq!(contract.listar_por_autor(1, 0, 1).1, Some(1));
+
+            let alteracoes = AtualizacaoLivro {
+                autores: Some(vec![1]),
+                ..AtualizacaoLivro::default()
+            };
+            assert_eq!(contract.atualizar_livro(b, alteracoes), Ok(()));
+            assert!(ids(&contract, clarice).is_empty());
+        }
+
+        #[ink::test]
+        fn test_autor_referenciado_nao_pode_ser_removido() {
+            let mut contract = contrato_com_autor();
+            let id = contract
+                .adicionar_livro("Dom Casmurro".into(), vec![GENERO_ROMANCE], metadados_completos())
+                .unwrap();
+            assert_eq!(contract.remover_autor(1), Err(Error::AutorReferenciado));
+            assert_eq!(contract.remover_livro(id), Ok(()));
+            assert_eq!(contract.remover_autor(1), Ok(()));
+            assert_eq!(contract.autor(1), None);
+            assert_eq!(contract.remover_autor(1), Err(Error::AutorNaoEncontrado));
+        }
+
+        #[ink::test]
+        fn test_tokens_dos_exemplares() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let biblioteca = ink::env::test::callee::<ink::env::DefaultEnvironment>();
+            let id = contract
+                .adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default())
+                .unwrap();
+            let segundo = contract.adicionar_exemplar(id, Conservacao::Bom).unwrap();
+            let primeiro = contract.exemplares(id)[0].id;
+            assert_eq!(PSP34::total_supply(&contract), 2);
+            assert_eq!(PSP34::balance_of(&contract, biblioteca), 2);
+            assert_eq!(contract.owner_of(Id::U32(primeiro)), Some(biblioteca));
+            assert_eq!(contract.owner_of(Id::U32(99)), None);
+
+            registrar(&mut contract, contas.bob);
+            definir_chamador(contas.bob);
+            let exemplar_id = contract.emprestar_livro(id).unwrap();
+            assert_eq!(contract.owner_of(Id::U32(exemplar_id)), Some(contas.bob));
+            assert_eq!(PSP34::balance_of(&contract, contas.bob), 1);
+            assert_eq!(PSP34::balance_of(&contract, biblioteca), 1);
+            // O token fica bloqueado enquanto está com o tomador.
+            assert_eq!(
+                PSP34::transfer(&mut contract, contas.charlie, Id::U32(exemplar_id), Vec::new()),
+                Err(PSP34Error::Custom("ExemplarBloqueado".into()))
+            );
+            assert_eq!(
+                PSP34::transfer(&mut contract, contas.charlie, Id::U32(segundo), Vec::new()),
+                Err(PSP34Error::NotApproved)
+            );
+
+            assert_eq!(contract.devolver_livro(exemplar_id), Ok(()));
+            assert_eq!(contract.owner_of(Id::U32(exemplar_id)), Some(biblioteca));
+            assert_eq!(PSP34::balance_of(&contract, contas.bob), 0);
+
+            definir_chamador(contas.alice);
+            assert_eq!(contract.retirar_exemplar(segundo), Ok(()));
+            assert_eq!(contract.owner_of(Id::U32(segundo)), None);
+            assert_eq!(PSP34::total_supply(&contract), 1);
+
+            // O token de um exemplar perdido é queimado.
+            definir_chamador(contas.bob);
+            let exemplar_id = contract.emprestar_livro(id).unwrap();
+            definir_chamador(contas.alice);
+            assert_eq!(contract.marcar_perdido(exemplar_id), Ok(()));
+            assert_eq!(contract.owner_of(Id::U32(exemplar_id)), None);
+            assert_eq!(PSP34::balance_of(&contract, contas.bob), 0);
+            assert_eq!(PSP34::total_supply(&contract), 0);
+
+            assert_eq!(contract.remover_livro(id), Ok(()));
+            assert_eq!(PSP34::total_supply(&contract), 0);
+            assert_eq!(PSP34::balance_of(&contract, biblioteca), 0);
+        }
+
+        #[ink::test]
+        fn test_pontos_por_devolucao_no_prazo() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let configuracao = Configuracao {
+                pontos_por_devolucao: 30,
+                maximo_pontos_por_membro: 50,
+                ..Configuracao::default()
+            };
+            contract.definir_configuracao(configuracao).unwrap();
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            registrar(&mut contract, contas.bob);
+
+            definir_chamador(contas.bob);
+            // Devolver antes de `duracao_minima_para_pontos` não rende pontos.
+            let exemplar_id = contract.emprestar_livro(id).unwrap();
+            assert_eq!(contract.devolver_livro(exemplar_id), Ok(()));
+            assert_eq!(PSP22::balance_of(&contract, contas.bob), 0);
+
+            let exemplar_id = contract.emprestar_livro(id).unwrap();
+            definir_instante(DIA);
+            assert_eq!(contract.devolver_livro(exemplar_id), Ok(()));
+            assert_eq!(PSP22::balance_of(&contract, contas.bob), 30);
+            // O saldo de cada membro é limitado por `maximo_pontos_por_membro`.
+            let exemplar_id = contract.emprestar_livro(id).unwrap();
+            definir_instante(2 * DIA);
+            assert_eq!(contract.devolver_livro(exemplar_id), Ok(()));
+            assert_eq!(PSP22::balance_of(&contract, contas.bob), 50);
+            assert_eq!(PSP22::total_supply(&contract), 50);
+
+            // O limite também vale para os pontos recebidos por transferência.
+            assert_eq!(PSP22::transfer(&mut contract, contas.charlie, 50, Vec::new()), Ok(()));
+            contract.emitir_pontos(contas.bob, 10);
+            assert_eq!(
+                PSP22::transfer(&mut contract, contas.charlie, 10, Vec::new()),
+                Err(PSP22Error::Custom("LimiteDePontos".into()))
+            );
+            assert_eq!(PSP22::balance_of(&contract, contas.bob), 10);
+
+            // Devoluções atrasadas não rendem pontos.
+            let exemplar_id = contract.emprestar_livro(id).unwrap();
+            definir_instante(3 * DIA + PRAZO_EMPRESTIMO + 1);
+            assert_eq!(contract.devolver_livro(exemplar_id), Ok(()));
+            assert_eq!(PSP22::balance_of(&contract, contas.bob), 10);
+            assert_eq!(PSP22::balance_of(&contract, contas.charlie), 50);
+        }
+
+        #[ink::test]
+        fn test_permissoes_de_pontos() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            contract.emitir_pontos(contas.bob, 100);
+
+            definir_chamador(contas.bob);
+            assert_eq!(PSP22::approve(&mut contract, contas.charlie, 40), Ok(()));
+            assert_eq!(PSP22::increase_allowance(&mut contract, contas.charlie, 10), Ok(()));
+            assert_eq!(PSP22::allowance(&contract, contas.bob, contas.charlie), 50);
+            assert_eq!(
+                PSP22::transfer(&mut contract, contas.charlie, 101, Vec::new()),
+                Err(PSP22Error::InsufficientBalance)
+            );
+
+            definir_chamador(contas.charlie);
+            assert_eq!(
+                PSP22::transfer_from(&mut contract, contas.bob, contas.django, 60, Vec::new()),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+            assert_eq!(PSP22::transfer_from(&mut contract, contas.bob, contas.django, 30, Vec::new()), Ok(()));
+            assert_eq!(PSP22::allowance(&contract, contas.bob, contas.charlie), 20);
+            assert_eq!(PSP22::balance_of(&contract, contas.bob), 70);
+            assert_eq!(PSP22::balance_of(&contract, contas.django), 30);
+            assert_eq!(PSP22::total_supply(&contract), 100);
+        }
+
+        #[ink::test]
+        fn test_gastar_pontos() {
+            let contas = contas();
+            let mut contract = contrato_com_atraso(3);
+            let devido = contract.multa_pendente(contas.bob);
+            let valor_do_ponto = contract.configuracao().valor_do_ponto;
+            contract.emitir_pontos(contas.bob, 1_000);
+
+            definir_chamador(contas.bob);
+            let necessarios = devido.div_ceil(valor_do_ponto);
+            assert_eq!(contract.pagar_multa_com_pontos(necessarios + 1), Err(Error::ValorInvalido));
+            assert_eq!(contract.pagar_multa_com_pontos(1), Ok(devido - valor_do_ponto));
+            assert_eq!(contract.pagar_multa_com_pontos(necessarios - 1), Ok(0));
+            assert_eq!(PSP22::balance_of(&contract, contas.bob), 1_000 - necessarios);
+
+            definir_chamador(contas.alice);
+            let configuracao = Configuracao {
+                taxa_reserva: 25,
+                ..contract.configuracao()
+            };
+            contract.definir_configuracao(configuracao).unwrap();
+            let id = contract.adicionar_livro("Livro B".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            registrar(&mut contract, contas.charlie);
+            definir_chamador(contas.charlie);
+            contract.emprestar_livro(id).unwrap();
+
+            definir_chamador(contas.bob);
+            assert_eq!(contract.reservar_livro(id), Err(Error::ValorInvalido));
+            let saldo = PSP22::balance_of(&contract, contas.bob);
+            assert_eq!(contract.reservar_livro_com_pontos(id), Ok(1));
+            assert_eq!(PSP22::balance_of(&contract, contas.bob), saldo - 3);
+        }
+
+        #[ink::test]
+        fn test_pagamentos_com_token_exigem_configuracao() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            contract.definir_caucao(id, 1_000).unwrap();
+            contract.registrar_membro("Alice".into()).unwrap();
+            assert_eq!(
+                contract.comprar_assinatura_com_token(NivelMembro::Basico),
+                Err(Error::TokenNaoConfigurado)
+            );
+            contract.comprar_assinatura(NivelMembro::Basico).unwrap();
+            assert_eq!(contract.emprestar_livro_com_token(id), Err(Error::TokenNaoConfigurado));
+            assert_eq!(
+                contract.sacar_fundos_em_token(contas.django, 1, contas.alice),
+                Err(Error::SaldoInsuficiente)
+            );
+
+            let configuracao = Configuracao {
+                token_de_pagamento: Some(contas.django),
+                ..contract.configuracao()
+            };
+            contract.definir_configuracao(configuracao).unwrap();
+            assert_eq!(contract.pagar_multa_com_token(1), Err(Error::ValorInvalido));
+            // Valores nulos não geram chamadas ao token.
+            assert!(contract.renovar_assinatura_com_token().is_ok());
+            assert_eq!(contract.fundos_em_token(contas.django), 0);
+        }
+
+        #[ink::test]
+        fn test_caucao_em_token_usa_o_token_da_retirada() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            let configuracao = Configuracao {
+                token_de_pagamento: Some(contas.django),
+                ..contract.configuracao()
+            };
+            contract.definir_configuracao(configuracao.clone()).unwrap();
+            registrar(&mut contract, contas.bob);
+            definir_chamador(contas.bob);
+            let exemplar_id = contract.emprestar_livro_com_token(id).unwrap();
+            contract.creditar(Moeda::Token(contas.django), 500);
+
+            definir_chamador(contas.alice);
+            let configuracao = Configuracao {
+                token_de_pagamento: Some(contas.eve),
+                ..configuracao
+            };
+            contract.definir_configuracao(configuracao).unwrap();
+            assert_eq!(
+                contract.emprestimos.get(exemplar_id).unwrap().moeda_da_caucao,
+                Moeda::Token(contas.django)
+            );
+            assert_eq!(contract.fundos_em_token(contas.django), 500);
+            assert_eq!(contract.fundos_em_token(contas.eve), 0);
+            assert_eq!(
+                contract.sacar_fundos_em_token(contas.eve, 1, contas.alice),
+                Err(Error::SaldoInsuficiente)
+            );
+
+            // A devolução não depende do token configurado.
+            let configuracao = Configuracao {
+                token_de_pagamento: None,
+                ..contract.configuracao()
+            };
+            contract.definir_configuracao(configuracao).unwrap();
+            definir_chamador(contas.bob);
+            assert_eq!(contract.devolver_livro(exemplar_id), Ok(()));
+        }
+
+        #[ink::test]
+        fn test_erros_do_token_de_pagamento() {
+            assert_eq!(erro_do_token(Ok(Ok(Ok(())))), Ok(()));
+            assert_eq!(
+                erro_do_token(Ok(Ok(Err(PSP22Error::InsufficientBalance)))),
+                Err(Error::SaldoDeTokenInsuficiente)
+            );
+            assert_eq!(
+                erro_do_token(Ok(Ok(Err(PSP22Error::InsufficientAllowance)))),
+                Err(Error::PermissaoDeTokenInsuficiente)
+            );
+            assert_eq!(
+                erro_do_token(Ok(Ok(Err(PSP22Error::Custom("pausado".into()))))),
+                Err(Error::FalhaNoToken)
+            );
+            assert_eq!(
+                erro_do_token(Ok(Err(ink::LangError::CouldNotReadInput))),
+                Err(Error::FalhaNoToken)
+            );
+            assert_eq!(erro_do_token(Err(ink::env::Error::CalleeTrapped)), Err(Error::FalhaNoToken));
+        }
+
+        #[ink::test]
+        fn test_pausar_bloqueia_alteracoes() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            let id = contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()).unwrap();
+            definir_chamador(contas.bob);
+            assert_eq!(contract.pausar(None), Err(Error::NaoAutorizado));
+
+            definir_chamador(contas.alice);
+            assert_eq!(contract.pausar(None), Ok(()));
+            assert!(contract.pausado());
+            assert_eq!(
+                contract.adicionar_livro("Livro B".into(), vec![GENERO_FICCAO], Metadados::default()),
+                Err(Error::ContratoPausado)
+            );
+            assert_eq!(contract.atualizar_livro(id, renomear("Novo".into(), GENERO_POESIA)), Err(Error::ContratoPausado));
+            assert_eq!(contract.remover_livro(id), Err(Error::ContratoPausado));
+            assert_eq!(contract.registrar_membro("Alice".into()), Err(Error::ContratoPausado));
+            assert_eq!(
+                PSP22::transfer(&mut contract, contas.bob, 0, Vec::new()),
+                Err(PSP22Error::Custom("ContratoPausado".into()))
+            );
+            // As consultas continuam disponíveis.
+            assert_eq!(contract.obter_livro(id).unwrap().titulo, "Livro A");
+
+            assert_eq!(contract.despausar(), Ok(()));
+            assert!(!contract.pausado());
+            assert_eq!(contract.remover_livro(id), Ok(()));
+            let eventos = eventos();
+            assert!(eventos.iter().any(|evento| matches!(evento, Evento::Pausado(_))));
+            assert!(eventos.iter().any(|evento| matches!(evento, Evento::Despausado(_))));
+        }
+
+        #[ink::test]
+        fn test_pausa_com_prazo() {
+            let mut contract = BibliotecaStorage::new();
+            definir_instante(DIA);
+            assert_eq!(contract.pausar(Some(DIA)), Err(Error::ValorInvalido));
+            assert_eq!(contract.pausar(Some(2 * DIA)), Ok(()));
+            assert_eq!(
+                contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()),
+                Err(Error::ContratoPausado)
+            );
+            definir_instante(2 * DIA);
+            assert!(!contract.pausado());
+            assert!(contract
+                .adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default())
+                .is_ok());
+        }
+
+        #[ink::test]
+        fn test_raiz_anterior_a_atualizacao_de_codigo() {
+            use ink::storage::traits::Storable;
+            use scale::Encode;
+            let contas = contas();
+            let configuracao = Configuracao {
+                taxa_reserva: 7,
+                ..Configuracao::default()
+            };
+            // Raiz gravada pelo código anterior a `atualizar_codigo`, campo a campo.
+            let mut raiz = Vec::new();
+            5u32.encode_to(&mut raiz); // total_livros
+            9u32.encode_to(&mut raiz); // proximo_id
+            VERSAO_STORAGE.encode_to(&mut raiz);
+            false.encode_to(&mut raiz); // pausado
+            None::<Timestamp>.encode_to(&mut raiz); // pausado_ate
+            contas.bob.encode_to(&mut raiz); // dono
+            Some(contas.charlie).encode_to(&mut raiz); // dono_pendente
+            4u32.encode_to(&mut raiz); // proximo_exemplar_id
+            configuracao.encode_to(&mut raiz);
+            Balance::from(300u32).encode_to(&mut raiz); // fundos_disponiveis
+            7u32.encode_to(&mut raiz); // proximo_genero_id
+            2u128.encode_to(&mut raiz); // total_de_tokens
+            Balance::from(40u32).encode_to(&mut raiz); // pontos_em_circulacao
+            3u32.encode_to(&mut raiz); // proximo_autor_id
+
+            let mut entrada = &raiz[..];
+            let contract = <BibliotecaStorage as Storable>::decode(&mut entrada).unwrap();
+            assert!(entrada.is_empty());
+            assert_eq!(contract.total_livros(), 5);
+            assert_eq!(contract.versao_storage(), VERSAO_STORAGE);
+            assert_eq!(contract.owner(), contas.bob);
+            assert_eq!(contract.pending_owner(), Some(contas.charlie));
+            assert_eq!(contract.configuracao(), configuracao);
+            assert_eq!(contract.fundos_disponiveis(), 300);
+            assert_eq!(contract.migracao_ate.get(), None);
+
+            let mut regravada = Vec::new();
+            Storable::encode(&contract, &mut regravada);
+            assert_eq!(regravada, raiz);
+        }
+
+        #[ink::test]
+        fn test_migrar_em_lotes() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            assert_eq!(contract.migrar(10), Ok(None));
+            // Simula um código novo sobre um armazenamento da versão anterior,
+            // com IDs já usados e livros removidos.
+            contract.proximo_id = 120;
+            contract.versao_storage = VERSAO_STORAGE - 1;
+            assert_eq!(
+                contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()),
+                Err(Error::MigracaoPendente)
+            );
+
+            definir_chamador(contas.bob);
+            assert_eq!(contract.migrar(10), Err(Error::NaoAutorizado));
+            definir_chamador(contas.alice);
+            assert_eq!(contract.migrar(MAXIMO_POR_PAGINA + 10), Ok(Some(1 + MAXIMO_POR_PAGINA)));
+            assert_eq!(contract.migrar(MAXIMO_POR_PAGINA), Ok(Some(1 + 2 * MAXIMO_POR_PAGINA)));
+            assert_eq!(contract.versao_storage(), VERSAO_STORAGE - 1);
+            assert_eq!(contract.migrar(MAXIMO_POR_PAGINA), Ok(None));
+            assert_eq!(contract.versao_storage(), VERSAO_STORAGE);
+            assert!(matches!(eventos().last(), Some(Evento::MigracaoConcluida(_))));
+            assert_eq!(
+                contract.adicionar_livro("Livro A".into(), vec![GENERO_FICCAO], Metadados::default()),
+                Ok(120)
+            );
+        }
+
+        #[ink::test]
+        fn test_consultas_durante_a_migracao() {
+            let mut contract = BibliotecaStorage::new();
+            let convertido = contract
+                .adicionar_livro("Dom Casmurro".into(), vec![GENERO_ROMANCE], Metadados::default())
+                .unwrap();
+            let pendente = contract
+                .adicionar_livro("Dom Quixote".into(), vec![GENERO_ROMANCE], Metadados::default())
+                .unwrap();
+            // Migração pela metade: só o primeiro livro está no layout atual.
+            contract.versao_storage = VERSAO_STORAGE - 1;
+            contract.migracao_ate.set(&pendente);
+
+            assert!(contract.obter_livro(convertido).is_some());
+            assert_eq!(contract.obter_livro(pendente), None);
+            assert!(contract.existe_livro(pendente));
+            let livros = contract.obter_livros(vec![convertido, pendente]).unwrap();
+            assert_eq!(livros[1], None);
+            assert_eq!(contract.listar_livros().len(), 1);
+            assert_eq!(contract.listar_livros_paginado(None, 10).0.len(), 1);
+            assert_eq!(contract.listar_por_genero(GENERO_ROMANCE, 0, 10).0.len(), 1);
+            assert_eq!(contract.buscar_por_titulo("dom".into(), 0, 10).0.len(), 2);
+            // Um livro que não está no layout atual interrompe a migração.
+            assert_eq!(contract.migrar(10), Err(Error::FalhaNaMigracao));
+
+            contract.versao_storage = VERSAO_STORAGE;
+            assert!(contract.obter_livro(pendente).is_some());
+            assert_eq!(contract.listar_livros().len(), 2);
+        }
+
+        #[ink::test]
+        fn test_atualizar_codigo_exige_dono() {
+            let contas = contas();
+            let mut contract = BibliotecaStorage::new();
+            definir_chamador(contas.bob);
+            assert_eq!(contract.atualizar_codigo(Hash::from([1; 32])), Err(Error::NaoAutorizado));
         }
     }
 }
@@ -0,0 +1,56 @@
+//! Interface PSP22 de tokens fungíveis, usada para os pontos de leitura.
+
+use ink::prelude::string::String;
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+
+/// Erros definidos pelo padrão PSP22.
+#[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP22Error {
+    /// Erro específico do contrato.
+    Custom(String),
+    /// O saldo do remetente é insuficiente.
+    InsufficientBalance,
+    /// A permissão concedida ao chamador é insuficiente.
+    InsufficientAllowance,
+    /// O contrato de destino recusou a transferência.
+    SafeTransferCheckFailed(String),
+}
+
+/// Mensagens do padrão PSP22, com os seletores definidos pelo padrão.
+#[ink::trait_definition]
+pub trait PSP22 {
+    /// Retorna a quantidade de tokens em circulação.
+    #[ink(message)]
+    fn total_supply(&self) -> u128;
+
+    /// Retorna o saldo de `owner`.
+    #[ink(message)]
+    fn balance_of(&self, owner: AccountId) -> u128;
+
+    /// Retorna quanto `spender` ainda pode transferir em nome de `owner`.
+    #[ink(message)]
+    fn allowance(&self, owner: AccountId, spender: AccountId) -> u128;
+
+    /// Transfere `value` tokens do chamador para `to`.
+    #[ink(message)]
+    fn transfer(&mut self, to: AccountId, value: u128, data: Vec<u8>) -> Result<(), PSP22Error>;
+
+    /// Transfere `value` tokens de `from` para `to`, consumindo a permissão
+    /// concedida ao chamador.
+    #[ink(message)]
+    fn transfer_from(&mut self, from: AccountId, to: AccountId, value: u128, data: Vec<u8>) -> Result<(), PSP22Error>;
+
+    /// Define em `value` a permissão de `spender` sobre os tokens do chamador.
+    #[ink(message)]
+    fn approve(&mut self, spender: AccountId, value: u128) -> Result<(), PSP22Error>;
+
+    /// Aumenta em `delta_value` a permissão de `spender`.
+    #[ink(message)]
+    fn increase_allowance(&mut self, spender: AccountId, delta_value: u128) -> Result<(), PSP22Error>;
+
+    /// Reduz em `delta_value` a permissão de `spender`.
+    #[ink(message)]
+    fn decrease_allowance(&mut self, spender: AccountId, delta_value: u128) -> Result<(), PSP22Error>;
+}
@@ -0,0 +1,73 @@
+//! Interface PSP34 de tokens não fungíveis, usada para representar cada
+//! exemplar do acervo.
+
+use ink::prelude::string::String;
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+
+/// Identificador de um token PSP34.
+#[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub enum Id {
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    Bytes(Vec<u8>),
+}
+
+/// Erros definidos pelo padrão PSP34.
+#[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP34Error {
+    /// Erro específico do contrato.
+    Custom(String),
+    /// O dono tentou aprovar a si mesmo.
+    SelfApprove,
+    /// O chamador não tem permissão sobre o token.
+    NotApproved,
+    /// O token já existe.
+    TokenExists,
+    /// O token não existe.
+    TokenNotExists,
+    /// O contrato de destino recusou o token.
+    SafeTransferCheckFailed(String),
+}
+
+/// Mensagens do padrão PSP34, com os seletores definidos pelo padrão.
+#[ink::trait_definition]
+pub trait PSP34 {
+    /// Retorna o identificador da coleção.
+    #[ink(message)]
+    fn collection_id(&self) -> Id;
+
+    /// Retorna a quantidade de tokens de `owner`.
+    #[ink(message)]
+    fn balance_of(&self, owner: AccountId) -> u32;
+
+    /// Retorna o dono do token `id`, se ele existir.
+    #[ink(message)]
+    fn owner_of(&self, id: Id) -> Option<AccountId>;
+
+    /// Indica se `operator` pode transferir o token `id`, ou todos os tokens
+    /// de `owner` quando `id` é `None`.
+    #[ink(message)]
+    fn allowance(&self, owner: AccountId, operator: AccountId, id: Option<Id>) -> bool;
+
+    /// Concede ou revoga a permissão de `operator` sobre o token `id`, ou
+    /// sobre todos os tokens do chamador quando `id` é `None`.
+    #[ink(message)]
+    fn approve(&mut self, operator: AccountId, id: Option<Id>, approved: bool) -> Result<(), PSP34Error>;
+
+    /// Transfere o token `id` para `to`.
+    #[ink(message)]
+    fn transfer(&mut self, to: AccountId, id: Id, data: Vec<u8>) -> Result<(), PSP34Error>;
+
+    /// Retorna a quantidade de tokens existentes.
+    #[ink(message)]
+    fn total_supply(&self) -> u128;
+}